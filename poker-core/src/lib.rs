@@ -1,5 +1,9 @@
-use rand::Rng;
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 pub const SMALL_BLIND_CHIPS: u64 = 10;
 pub const BIG_BLIND_CHIPS: u64 = 20;
@@ -30,6 +34,21 @@ pub enum PlayerAction {
     Bet,
     Raise,
     AllIn,
+    /// Bet so the player's total street bet becomes the given amount.
+    BetTo(u64),
+    /// Raise so the player's total street bet becomes the given amount.
+    RaiseTo(u64),
+}
+
+/// An action chosen in advance and played as soon as it is the player's turn.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AutoAction {
+    /// Check if possible, otherwise fold.
+    CheckFold,
+    /// Check if possible, otherwise call whatever is bet.
+    CheckCall,
+    /// Check if possible, call a bet costing up to this many chips, fold to a bigger one.
+    CallAny(u64),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
@@ -46,6 +65,82 @@ pub enum HandRank {
     RoyalFlush = 9,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Variant {
+    #[default]
+    TexasHoldem,
+    Omaha,
+    ShortDeck,
+}
+
+impl Variant {
+    #[must_use]
+    pub fn hole_card_count(self) -> usize {
+        match self {
+            Self::TexasHoldem | Self::ShortDeck => 2,
+            Self::Omaha => 4,
+        }
+    }
+
+    /// Lowest card rank present in the deck (short deck removes 2 through 5).
+    #[must_use]
+    pub fn lowest_rank(self) -> u8 {
+        match self {
+            Self::TexasHoldem | Self::Omaha => 2,
+            Self::ShortDeck => 6,
+        }
+    }
+
+    /// Relative strength of a hand category. Short deck ranks a flush above a
+    /// full house because flushes are harder to make with fewer cards per suit.
+    #[must_use]
+    pub fn rank_strength(self, rank: HandRank) -> u8 {
+        match (self, rank) {
+            (Self::ShortDeck, HandRank::Flush) => HandRank::FullHouse as u8,
+            (Self::ShortDeck, HandRank::FullHouse) => HandRank::Flush as u8,
+            _ => rank as u8,
+        }
+    }
+
+    #[must_use]
+    pub fn evaluate(self, hole_cards: &[Card], community_cards: &[Card]) -> EvaluatedHand {
+        match self {
+            Self::Omaha => match <&[Card; 4]>::try_from(hole_cards) {
+                Ok(hole) => PokerHandEvaluator::evaluate_omaha(hole, community_cards),
+                Err(_) => PokerHandEvaluator::evaluate(hole_cards, community_cards),
+            },
+            Self::TexasHoldem | Self::ShortDeck => {
+                PokerHandEvaluator::evaluate_for_variant(hole_cards, community_cards, self)
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn compare_hands(self, a: &EvaluatedHand, b: &EvaluatedHand) -> std::cmp::Ordering {
+        self.rank_strength(a.rank)
+            .cmp(&self.rank_strength(b.rank))
+            .then_with(|| a.cmp(b))
+    }
+}
+
+impl std::fmt::Display for HandRank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::HighCard => "High Card",
+            Self::Pair => "Pair",
+            Self::TwoPair => "Two Pair",
+            Self::ThreeOfAKind => "Three of a Kind",
+            Self::Straight => "Straight",
+            Self::Flush => "Flush",
+            Self::FullHouse => "Full House",
+            Self::FourOfAKind => "Four of a Kind",
+            Self::StraightFlush => "Straight Flush",
+            Self::RoyalFlush => "Royal Flush",
+        };
+        f.write_str(name)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
 pub enum Suit {
     Spades,
@@ -55,6 +150,8 @@ pub enum Suit {
 }
 
 impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
+
     #[must_use]
     pub fn to_char(self) -> char {
         match self {
@@ -139,6 +236,40 @@ impl Card {
 
         Some(Card::new(rank, suit))
     }
+
+    /// A distinct bit per card: thirteen bits per suit, lowest rank first.
+    #[must_use]
+    pub fn to_bit(&self) -> u64 {
+        1 << (self.suit as u32 * 13 + u32::from(self.rank) - 2)
+    }
+
+    /// Compare by rank alone, so a stable sort keeps equal ranks in their
+    /// original suit order.
+    #[must_use]
+    pub fn cmp_by_rank(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank.cmp(&other.rank)
+    }
+
+    /// The ranks held in each suit, in the order the cards appear.
+    #[must_use]
+    pub fn group_by_suit(cards: &[Card]) -> HashMap<Suit, Vec<u8>> {
+        let mut groups: HashMap<Suit, Vec<u8>> = HashMap::new();
+        for card in cards {
+            groups.entry(card.suit).or_default().push(card.rank);
+        }
+        groups
+    }
+}
+
+/// How `Deck::shuffle_with` mixes the cards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ShuffleMethod {
+    /// A uniform random permutation.
+    #[default]
+    FisherYates,
+    /// Dealer-style riffles: each pass cuts the deck near the middle and drops
+    /// cards alternately from the two halves. A few passes leave visible order.
+    Riffle { passes: u8 },
 }
 
 #[derive(Clone)]
@@ -155,23 +286,84 @@ impl Default for Deck {
 impl Deck {
     #[must_use]
     pub fn new() -> Self {
-        let mut cards = Vec::with_capacity(52);
-        for suit in [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs] {
-            for rank in 2..=14 {
-                cards.push(Card::new(rank, suit));
-            }
-        }
-        Deck { cards }
+        Self::for_variant(Variant::TexasHoldem)
+    }
+
+    #[must_use]
+    pub fn for_variant(variant: Variant) -> Self {
+        let mut deck = Deck {
+            cards: Vec::with_capacity(4 * usize::from(15 - variant.lowest_rank())),
+        };
+        deck.reset_for_variant(variant);
+        deck
+    }
+
+    /// Every card of the standard deck in canonical order: suit by suit, deuce to ace.
+    #[must_use]
+    pub fn all_cards() -> [Card; 52] {
+        std::array::from_fn(|i| Card::new(i as u8 % 13 + 2, Suit::ALL[i / 13]))
+    }
+
+    /// Refill to a full, ordered 52-card deck, reusing the existing allocation.
+    pub fn reset(&mut self) {
+        self.reset_for_variant(Variant::TexasHoldem);
+    }
+
+    /// Refill in place with the ordered cards `variant` plays with.
+    pub fn reset_for_variant(&mut self, variant: Variant) {
+        self.cards.clear();
+        let lowest = variant.lowest_rank();
+        self.cards
+            .extend(Self::all_cards().into_iter().filter(|c| c.rank >= lowest));
+    }
+
+    pub fn reset_and_shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.reset();
+        self.shuffle_with_rng(rng);
     }
 
     pub fn shuffle(&mut self) {
-        let mut rng = rand::thread_rng();
+        self.shuffle_with_rng(&mut rand::thread_rng());
+    }
+
+    pub fn shuffle_with_rng<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         for i in (1..self.cards.len()).rev() {
             let j = rng.gen_range(0..=i);
             self.cards.swap(i, j);
         }
     }
 
+    pub fn shuffle_with<R: Rng + ?Sized>(&mut self, method: ShuffleMethod, rng: &mut R) {
+        match method {
+            ShuffleMethod::FisherYates => self.shuffle_with_rng(rng),
+            ShuffleMethod::Riffle { passes } => {
+                for _ in 0..passes {
+                    self.riffle(rng);
+                }
+            }
+        }
+    }
+
+    /// One Gilbert-Shannon-Reeds riffle: a binomial cut, then each card falls from
+    /// a half with probability proportional to the cards left in it.
+    fn riffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let cut = (0..self.cards.len()).filter(|_| rng.gen_bool(0.5)).count();
+        let right = self.cards.split_off(cut);
+        let left = std::mem::take(&mut self.cards);
+        let (mut l, mut r) = (0, 0);
+        while l < left.len() || r < right.len() {
+            let left_remaining = left.len() - l;
+            let right_remaining = right.len() - r;
+            if rng.gen_range(0..left_remaining + right_remaining) < left_remaining {
+                self.cards.push(left[l]);
+                l += 1;
+            } else {
+                self.cards.push(right[r]);
+                r += 1;
+            }
+        }
+    }
+
     pub fn deal(&mut self, count: usize) -> Option<Vec<Card>> {
         if count > self.cards.len() {
             return None;
@@ -192,6 +384,59 @@ impl Deck {
     pub fn burn(&mut self) -> Option<Card> {
         self.cards.pop()
     }
+
+    /// True when the deck holds exactly the 52 distinct cards of a standard deck,
+    /// in any order.
+    #[must_use]
+    pub fn is_standard_complete(&self) -> bool {
+        self.is_complete_for(Variant::TexasHoldem)
+    }
+
+    /// True when the deck holds exactly the distinct cards `variant` plays with,
+    /// in any order.
+    #[must_use]
+    pub fn is_complete_for(&self, variant: Variant) -> bool {
+        let lowest = variant.lowest_rank();
+        let size = 4 * usize::from(15 - lowest);
+        let unique: HashSet<Card> = self.cards.iter().copied().collect();
+        self.cards.len() == size
+            && unique.len() == size
+            && unique.iter().all(|c| (lowest..=14).contains(&c.rank))
+    }
+}
+
+/// Running per-seat statistics across a session, updated from the event log at
+/// the end of every hand.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub hands_played: u64,
+    /// Hands where the player voluntarily put chips in preflop (blinds excluded).
+    pub vpip_hands: u64,
+    /// Hands where the player bet or raised preflop.
+    pub pfr_hands: u64,
+    /// Hands where the player collected any part of the pot.
+    pub hands_won: u64,
+    pub chips_won: u64,
+}
+
+impl PlayerStats {
+    #[must_use]
+    pub fn vpip(&self) -> f64 {
+        Self::ratio(self.vpip_hands, self.hands_played)
+    }
+
+    #[must_use]
+    pub fn pfr(&self) -> f64 {
+        Self::ratio(self.pfr_hands, self.hands_played)
+    }
+
+    fn ratio(count: u64, hands: u64) -> f64 {
+        if hands == 0 {
+            0.0
+        } else {
+            count as f64 / hands as f64
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -200,9 +445,16 @@ pub struct Player {
     chips: u64,
     hole_cards: Vec<Card>,
     current_bet: u64,
+    total_invested: u64,
     folded: bool,
     all_in: bool,
     acted: bool,
+    auto_muck: bool,
+    auto_action: Option<AutoAction>,
+    sitting_out: bool,
+    owes_blind: bool,
+    chip_history: Vec<u64>,
+    stats: PlayerStats,
 }
 
 impl Player {
@@ -211,11 +463,18 @@ impl Player {
         Player {
             name,
             chips,
-            hole_cards: Vec::with_capacity(2),
+            hole_cards: Vec::with_capacity(4),
             current_bet: 0,
+            total_invested: 0,
             folded: false,
             all_in: false,
             acted: false,
+            auto_muck: false,
+            auto_action: None,
+            sitting_out: false,
+            owes_blind: false,
+            chip_history: Vec::new(),
+            stats: PlayerStats::default(),
         }
     }
 
@@ -234,6 +493,7 @@ impl Player {
         }
         self.chips -= amount;
         self.current_bet += amount;
+        self.total_invested += amount;
         if self.chips == 0 {
             self.all_in = true;
         }
@@ -245,12 +505,34 @@ impl Player {
         self.chips += amount;
     }
 
+    /// Give back the part of a bet nobody matched.
+    pub fn refund_uncalled(&mut self, amount: u64) {
+        self.chips += amount;
+        self.current_bet = self.current_bet.saturating_sub(amount);
+        self.total_invested = self.total_invested.saturating_sub(amount);
+        if self.chips > 0 {
+            self.all_in = false;
+        }
+    }
+
+    pub fn add_chips(&mut self, amount: u64) {
+        self.chips += amount;
+    }
+
+    /// Clear the bet for a new street, returning what the player had put in on
+    /// the street just finished.
+    pub fn reset_street_bet(&mut self) -> u64 {
+        std::mem::take(&mut self.current_bet)
+    }
+
     pub fn reset_for_new_hand(&mut self) {
         self.hole_cards.clear();
         self.current_bet = 0;
+        self.total_invested = 0;
         self.folded = false;
         self.all_in = false;
         self.acted = false;
+        self.auto_action = None;
     }
 
     #[must_use]
@@ -258,6 +540,10 @@ impl Player {
         &self.name
     }
 
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     #[must_use]
     pub fn get_chips(&self) -> u64 {
         self.chips
@@ -268,6 +554,18 @@ impl Player {
         self.current_bet
     }
 
+    /// Chips put in over the whole hand, across every street.
+    #[must_use]
+    pub fn get_total_invested(&self) -> u64 {
+        self.total_invested
+    }
+
+    /// Chips put in on the current street.
+    #[must_use]
+    pub fn street_contribution(&self) -> u64 {
+        self.current_bet
+    }
+
     #[must_use]
     pub fn is_folded(&self) -> bool {
         self.folded
@@ -277,6 +575,24 @@ impl Player {
         self.folded = folded;
     }
 
+    /// Sit out from the next hand on, or come back. A sitting-out player is dealt
+    /// around and posts no blinds.
+    pub fn sit_out(&mut self, sitting_out: bool) {
+        self.sitting_out = sitting_out;
+    }
+
+    #[must_use]
+    pub fn is_sitting_out(&self) -> bool {
+        self.sitting_out
+    }
+
+    /// Whether the player missed blinds while sitting out and must post a big
+    /// blind when dealt back in.
+    #[must_use]
+    pub fn owes_blind(&self) -> bool {
+        self.owes_blind
+    }
+
     #[must_use]
     pub fn is_all_in(&self) -> bool {
         self.all_in
@@ -295,6 +611,37 @@ impl Player {
     pub fn get_hole_cards(&self) -> &[Card] {
         &self.hole_cards
     }
+
+    #[must_use]
+    pub fn get_stats(&self) -> &PlayerStats {
+        &self.stats
+    }
+
+    /// The player's stack at the end of each hand played so far, oldest first.
+    #[must_use]
+    pub fn get_chip_history(&self) -> &[u64] {
+        &self.chip_history
+    }
+
+    #[must_use]
+    pub fn is_auto_muck(&self) -> bool {
+        self.auto_muck
+    }
+
+    /// When set, the player mucks a losing hand at showdown instead of showing it.
+    pub fn set_auto_muck(&mut self, auto_muck: bool) {
+        self.auto_muck = auto_muck;
+    }
+
+    #[must_use]
+    pub fn get_auto_action(&self) -> Option<AutoAction> {
+        self.auto_action
+    }
+
+    /// Pre-select an action for the player's next turn this hand, or clear it.
+    pub fn set_auto_action(&mut self, auto_action: Option<AutoAction>) {
+        self.auto_action = auto_action;
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -313,6 +660,53 @@ impl EvaluatedHand {
             kickers,
         }
     }
+
+    /// Video-poker qualifier: a pair of jacks or better, or any higher category.
+    #[must_use]
+    pub fn qualifies_jacks_or_better(&self) -> bool {
+        match self.rank {
+            HandRank::HighCard => false,
+            HandRank::Pair => self.primary_values.first().is_some_and(|&pair| pair >= 11),
+            _ => true,
+        }
+    }
+}
+
+/// Tally of how often each hand category has been seen, e.g. at showdown over a
+/// long session.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HandStats {
+    counts: [u64; 10],
+}
+
+impl HandStats {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, hand: &EvaluatedHand) {
+        self.counts[hand.rank as usize] += 1;
+    }
+
+    #[must_use]
+    pub fn get_count(&self, rank: HandRank) -> u64 {
+        self.counts[rank as usize]
+    }
+
+    #[must_use]
+    pub fn get_total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    #[must_use]
+    pub fn frequency(&self, rank: HandRank) -> f64 {
+        let total = self.get_total();
+        if total == 0 {
+            return 0.0;
+        }
+        self.get_count(rank) as f64 / total as f64
+    }
 }
 
 pub struct PokerHandEvaluator;
@@ -320,6 +714,31 @@ pub struct PokerHandEvaluator;
 impl PokerHandEvaluator {
     #[must_use]
     pub fn evaluate(hole_cards: &[Card], community_cards: &[Card]) -> EvaluatedHand {
+        Self::evaluate_for_variant(hole_cards, community_cards, Variant::TexasHoldem)
+    }
+
+    /// Evaluate a hand using the straight rules of `variant`. In short deck the
+    /// wheel is A-6-7-8-9; compare the results with `Variant::compare_hands` so a
+    /// flush also outranks a full house.
+    #[must_use]
+    pub fn evaluate_for_variant(
+        hole_cards: &[Card],
+        community_cards: &[Card],
+        variant: Variant,
+    ) -> EvaluatedHand {
+        Self::evaluate_with_wheel(hole_cards, community_cards, variant, true)
+    }
+
+    /// Like `evaluate_for_variant`, but with `allow_wheel` false the ace only
+    /// plays high, so A-2-3-4-5 is just ace-high.
+    #[must_use]
+    pub fn evaluate_with_wheel(
+        hole_cards: &[Card],
+        community_cards: &[Card],
+        variant: Variant,
+        allow_wheel: bool,
+    ) -> EvaluatedHand {
+        let wheel_low = allow_wheel.then_some(variant.lowest_rank());
         let mut all_cards: Vec<Card> = Vec::with_capacity(hole_cards.len() + community_cards.len());
         all_cards.extend(hole_cards);
         all_cards.extend(community_cards);
@@ -333,16 +752,15 @@ impl PokerHandEvaluator {
         let mut ranks_dedup = ranks.clone();
         ranks_dedup.dedup();
 
-        let rank_counts: HashMap<u8, usize> =
-            ranks.iter().fold(HashMap::new(), |mut map, &rank| {
-                *map.entry(rank).or_insert(0) += 1;
-                map
-            });
+        let mut rank_counts = [0u8; 15];
+        let mut suit_counts = [0u8; 4];
+        for card in &all_cards {
+            rank_counts[usize::from(card.rank)] += 1;
+            suit_counts[card.suit as usize] += 1;
+        }
+        let count_of = |rank: u8| rank_counts[usize::from(rank)];
 
-        if let Some(four_rank) = rank_counts
-            .iter()
-            .find_map(|(&rank, &count)| (count == 4).then_some(rank))
-        {
+        if let Some(&four_rank) = ranks_dedup.iter().find(|&&r| count_of(r) == 4) {
             let kickers: Vec<u8> = ranks
                 .iter()
                 .copied()
@@ -352,33 +770,19 @@ impl PokerHandEvaluator {
             return EvaluatedHand::new(HandRank::FourOfAKind, vec![four_rank], kickers);
         }
 
-        let full_house_ranks: Vec<u8> = rank_counts
-            .iter()
-            .filter(|(_, &count)| count >= 2)
-            .map(|(&rank, _)| rank)
-            .collect();
-        if full_house_ranks.len() >= 2 {
-            let three_rank = full_house_ranks.iter().find(|&&r| rank_counts[&r] >= 3);
-            if let Some(&three) = three_rank {
-                let pair_rank = full_house_ranks
-                    .iter()
-                    .find(|&&r| r != three && rank_counts[&r] >= 2);
-                if let Some(&pair) = pair_rank {
-                    return EvaluatedHand::new(HandRank::FullHouse, vec![three, pair], Vec::new());
-                }
+        if let Some(&three) = ranks_dedup.iter().find(|&&r| count_of(r) >= 3) {
+            let pair_rank = ranks_dedup
+                .iter()
+                .find(|&&r| r != three && count_of(r) >= 2);
+            if let Some(&pair) = pair_rank {
+                return EvaluatedHand::new(HandRank::FullHouse, vec![three, pair], Vec::new());
             }
         }
 
-        let suit_counts: HashMap<Suit, usize> =
-            all_cards.iter().fold(HashMap::new(), |mut map, &card| {
-                *map.entry(card.suit).or_insert(0) += 1;
-                map
-            });
-
-        let flush_suit = suit_counts
-            .iter()
-            .find(|(_, &count)| count >= 5)
-            .map(|(&suit, _)| suit);
+        let flush_suit = Suit::ALL
+            .into_iter()
+            .filter(|&suit| suit_counts[suit as usize] >= 5)
+            .max_by_key(|&suit| (suit_counts[suit as usize], suit));
         if let Some(suit) = flush_suit {
             let mut flush_cards: Vec<u8> = all_cards
                 .iter()
@@ -387,7 +791,7 @@ impl PokerHandEvaluator {
                 .collect();
             flush_cards.sort_unstable_by(|a, b| b.cmp(a));
 
-            if let Some(straight_ranks) = Self::find_straight(&flush_cards) {
+            if let Some(straight_ranks) = Self::find_straight(&flush_cards, wheel_low) {
                 if straight_ranks[0] == 14 && straight_ranks[1] == 13 {
                     return EvaluatedHand::new(HandRank::RoyalFlush, vec![14], Vec::new());
                 }
@@ -398,14 +802,11 @@ impl PokerHandEvaluator {
             return EvaluatedHand::new(HandRank::Flush, top_flush_cards, Vec::new());
         }
 
-        if let Some(straight_ranks) = Self::find_straight(&ranks_dedup) {
+        if let Some(straight_ranks) = Self::find_straight(&ranks_dedup, wheel_low) {
             return EvaluatedHand::new(HandRank::Straight, straight_ranks, Vec::new());
         }
 
-        if let Some(three_rank) = rank_counts
-            .iter()
-            .find_map(|(&rank, &count)| (count == 3).then_some(rank))
-        {
+        if let Some(&three_rank) = ranks_dedup.iter().find(|&&r| count_of(r) == 3) {
             let kickers: Vec<u8> = ranks
                 .iter()
                 .copied()
@@ -415,16 +816,14 @@ impl PokerHandEvaluator {
             return EvaluatedHand::new(HandRank::ThreeOfAKind, vec![three_rank], kickers);
         }
 
-        let two_pair_ranks: Vec<u8> = rank_counts
+        let two_pair_ranks: Vec<u8> = ranks_dedup
             .iter()
-            .filter(|(_, &count)| count == 2)
-            .map(|(&rank, _)| rank)
+            .copied()
+            .filter(|&r| count_of(r) == 2)
             .collect();
         if two_pair_ranks.len() >= 2 {
-            let mut pairs = two_pair_ranks;
-            pairs.sort_unstable_by(|a, b| b.cmp(a));
-            let first_pair = pairs[0];
-            let second_pair = pairs[1];
+            let first_pair = two_pair_ranks[0];
+            let second_pair = two_pair_ranks[1];
             let kicker: Vec<u8> = ranks
                 .iter()
                 .copied()
@@ -448,783 +847,6799 @@ impl PokerHandEvaluator {
         EvaluatedHand::new(HandRank::HighCard, Vec::new(), kickers)
     }
 
-    fn find_straight(ranks: &[u8]) -> Option<Vec<u8>> {
-        if ranks.len() < 5 {
-            return None;
+    /// Allocation-free seven-card evaluation for hot loops. The `HandRank` sits in
+    /// bits 20 and up with the tiebreak ranks in four-bit slots below, so scores
+    /// compare exactly like the hands `evaluate` returns.
+    #[must_use]
+    pub fn evaluate_fast(cards: &[Card; 7]) -> u32 {
+        let mut rank_counts = [0u8; 15];
+        let mut suit_masks = [0u16; 4];
+        for card in cards {
+            rank_counts[usize::from(card.rank)] += 1;
+            suit_masks[card.suit as usize] |= 1 << card.rank;
+        }
+        let rank_mask = suit_masks.iter().fold(0, |acc, mask| acc | mask);
+        let ranks_with = |min_count: u8, exclude: [u8; 2]| {
+            (2..=14u8)
+                .rev()
+                .filter(move |&r| rank_counts[usize::from(r)] >= min_count && !exclude.contains(&r))
+        };
+        let score = |rank: HandRank, values: &mut dyn Iterator<Item = u8>| {
+            let mut slots = [0u8; 5];
+            for (slot, value) in slots.iter_mut().zip(values) {
+                *slot = value;
+            }
+            slots
+                .iter()
+                .fold(rank as u32, |acc, &v| (acc << 4) | u32::from(v))
+        };
+        let straight = |top: u8| (0..5).map(move |i| top - i);
+
+        if let Some(&suit_mask) = suit_masks.iter().find(|m| m.count_ones() >= 5) {
+            return match Self::straight_top(suit_mask) {
+                Some(14) => score(HandRank::RoyalFlush, &mut std::iter::once(14)),
+                Some(top) => score(HandRank::StraightFlush, &mut straight(top)),
+                None => score(
+                    HandRank::Flush,
+                    &mut (2..=14u8).rev().filter(|&r| suit_mask & (1 << r) != 0),
+                ),
+            };
         }
 
-        let mut sorted_ranks = ranks.to_vec();
-        sorted_ranks.sort_unstable();
-        sorted_ranks.dedup();
+        if let Some(quads) = ranks_with(4, [0, 0]).next() {
+            let kicker = ranks_with(1, [quads, 0]).next();
+            return score(
+                HandRank::FourOfAKind,
+                &mut std::iter::once(quads).chain(kicker),
+            );
+        }
 
-        for i in 0..=sorted_ranks.len().saturating_sub(5) {
-            let window = &sorted_ranks[i..i + 5];
-            if window.windows(2).all(|w| w[1] == w[0] + 1) {
-                return Some(window.iter().rev().copied().collect());
+        let trips = ranks_with(3, [0, 0]).next();
+        if let Some(three) = trips {
+            if let Some(pair) = ranks_with(2, [three, 0]).next() {
+                return score(HandRank::FullHouse, &mut [three, pair].into_iter());
             }
         }
 
-        if sorted_ranks.contains(&14) {
-            let mut ace_low_ranks: Vec<u8> = sorted_ranks
-                .iter()
-                .copied()
-                .map(|r| if r == 14 { 1 } else { r })
-                .collect();
-            ace_low_ranks.sort_unstable();
-            ace_low_ranks.dedup();
+        if let Some(top) = Self::straight_top(rank_mask) {
+            return score(HandRank::Straight, &mut straight(top));
+        }
+
+        if let Some(three) = trips {
+            let kickers = ranks_with(1, [three, 0]).take(2);
+            return score(
+                HandRank::ThreeOfAKind,
+                &mut std::iter::once(three).chain(kickers),
+            );
+        }
+
+        let mut pairs = ranks_with(2, [0, 0]);
+        match (pairs.next(), pairs.next()) {
+            (Some(first), Some(second)) => {
+                let kicker = ranks_with(1, [first, second]).take(1);
+                score(
+                    HandRank::TwoPair,
+                    &mut [first, second].into_iter().chain(kicker),
+                )
+            }
+            (Some(pair), None) => {
+                let kickers = ranks_with(1, [pair, 0]).take(3);
+                score(HandRank::Pair, &mut std::iter::once(pair).chain(kickers))
+            }
+            _ => score(HandRank::HighCard, &mut ranks_with(1, [0, 0])),
+        }
+    }
+
+    /// Top rank of the highest straight in a rank bitmask (bit `r` set for rank
+    /// `r`), with the ace also playing low.
+    fn straight_top(mask: u16) -> Option<u8> {
+        let mask = mask | ((mask >> 14) & 1) << 1;
+        (5..=14u8).rev().find(|&top| {
+            let window = 0b1_1111 << (top - 4);
+            mask & window == window
+        })
+    }
 
-            for i in 0..=ace_low_ranks.len().saturating_sub(5) {
-                let window = &ace_low_ranks[i..i + 5];
-                if window.windows(2).all(|w| w[1] == w[0] + 1) {
-                    return Some(vec![5, 4, 3, 2, 1]);
+    /// Evaluate an Omaha hand, which must use exactly two of the four hole cards
+    /// and exactly three community cards.
+    #[must_use]
+    pub fn evaluate_omaha(hole_cards: &[Card; 4], community_cards: &[Card]) -> EvaluatedHand {
+        let mut best_hand: Option<EvaluatedHand> = None;
+        let board_len = community_cards.len();
+
+        for i in 0..hole_cards.len() {
+            for j in (i + 1)..hole_cards.len() {
+                let hole = [hole_cards[i], hole_cards[j]];
+                for a in 0..board_len {
+                    for b in (a + 1)..board_len {
+                        for c in (b + 1)..board_len {
+                            let board =
+                                [community_cards[a], community_cards[b], community_cards[c]];
+                            let hand = Self::evaluate(&hole, &board);
+                            if best_hand.as_ref().is_none_or(|best| hand > *best) {
+                                best_hand = Some(hand);
+                            }
+                        }
+                    }
                 }
             }
         }
 
-        None
+        best_hand.unwrap_or_else(|| EvaluatedHand::new(HandRank::HighCard, Vec::new(), Vec::new()))
     }
-}
 
-#[derive(Clone)]
-pub struct PokerGameState {
-    pub deck: Deck,
-    pub players: Vec<Player>,
-    pub community_cards: Vec<Card>,
-    pub pot: u64,
-    pub stage: GameStage,
-    pub dealer_position: usize,
-    pub current_player: usize,
-    pub to_call: u64,
-    pub pending_action: bool,
-    pub bet_amount: u64,
-    pub min_bet: u64,
-    pub max_bet: u64,
-    pub pot_odds: f32,
-}
+    /// The strongest hand anyone could hold on `community_cards`, found by trying
+    /// every pair of hole cards left in the deck.
+    #[must_use]
+    pub fn nut_hand(community_cards: &[Card]) -> EvaluatedHand {
+        let remaining: Vec<Card> = Deck::new()
+            .cards
+            .into_iter()
+            .filter(|c| !community_cards.contains(c))
+            .collect();
 
-impl PokerGameState {
+        let mut best_hand: Option<EvaluatedHand> = None;
+        for (i, &first) in remaining.iter().enumerate() {
+            for &second in &remaining[i + 1..] {
+                let hand = Self::evaluate(&[first, second], community_cards);
+                if best_hand.as_ref().is_none_or(|best| hand > *best) {
+                    best_hand = Some(hand);
+                }
+            }
+        }
+
+        best_hand.unwrap_or_else(|| EvaluatedHand::new(HandRank::HighCard, Vec::new(), Vec::new()))
+    }
+
+    /// The five actual cards forming the best hand, highest rank first. With fewer
+    /// than five cards available, all of them are returned.
     #[must_use]
-    pub fn new() -> Self {
-        let mut deck = Deck::new();
-        deck.shuffle();
+    pub fn best_five(hole_cards: &[Card], community_cards: &[Card]) -> Vec<Card> {
+        let all_cards: Vec<Card> = hole_cards.iter().chain(community_cards).copied().collect();
+        let mut best: Option<(EvaluatedHand, [Card; 5])> = None;
+        for five in Self::five_card_hands(&all_cards) {
+            let hand = Self::evaluate(&five, &[]);
+            if best.as_ref().is_none_or(|(best_hand, _)| hand > *best_hand) {
+                best = Some((hand, five));
+            }
+        }
 
-        let mut players = Vec::with_capacity(NUM_PLAYERS);
-        players.push(Player::new("Alice".to_string(), INITIAL_CHIPS));
-        players.push(Player::new("Bob".to_string(), INITIAL_CHIPS));
+        let mut cards = best.map_or(all_cards, |(_, five)| five.to_vec());
+        cards.sort_by(|a, b| b.cmp_by_rank(a));
+        cards
+    }
 
-        PokerGameState {
-            deck,
-            players,
-            community_cards: Vec::with_capacity(5),
-            pot: 0,
-            stage: GameStage::WaitingToStart,
-            dealer_position: 0,
-            current_player: 0,
-            to_call: 0,
-            pending_action: false,
-            bet_amount: CALL_AMOUNT_DEFAULT,
-            min_bet: MIN_BET_DEFAULT,
-            max_bet: MAX_BET_DEFAULT,
-            pot_odds: 0.0,
+    /// Every five-card combination of `cards`.
+    fn five_card_hands(cards: &[Card]) -> Vec<[Card; 5]> {
+        let n = cards.len();
+        let mut hands = Vec::new();
+        for a in 0..n {
+            for b in a + 1..n {
+                for c in b + 1..n {
+                    for d in c + 1..n {
+                        for e in d + 1..n {
+                            hands.push([cards[a], cards[b], cards[c], cards[d], cards[e]]);
+                        }
+                    }
+                }
+            }
         }
+        hands
     }
 
-    /// Start a new hand, dealing cards to all players.
-    ///
-    /// # Errors
-    ///
-    /// Returns `Err("Not enough players with sufficient chips")` if fewer than 2 players
-    /// have at least MIN_CHIPS_TO_CONTINUE chips.
-    pub fn start_new_hand(&mut self) -> Result<(), &'static str> {
-        if self
-            .players
+    /// Best deuce-to-seven low from `cards`: aces are high and straights and
+    /// flushes count against the hand. The result orders like a high hand, so the
+    /// smaller of two values is the better low; 7-5-4-3-2 offsuit is the nuts.
+    /// `None` for fewer than five cards.
+    #[must_use]
+    pub fn evaluate_low_2to7(cards: &[Card]) -> Option<EvaluatedHand> {
+        if cards.len() < 5 {
+            return None;
+        }
+        Self::five_card_hands(cards)
             .iter()
-            .filter(|p| p.get_chips() >= MIN_CHIPS_TO_CONTINUE)
-            .count()
-            < 2
-        {
-            return Err("Not enough players with sufficient chips");
+            .map(|five| Self::evaluate_with_wheel(five, &[], Variant::TexasHoldem, false))
+            .min()
+    }
+
+    /// Best eight-or-better low from `cards`, or `None` if there are not five
+    /// different ranks of eight or lower. Aces play low (as 1) and straights and
+    /// flushes are ignored. `primary_values` holds the five ranks highest first,
+    /// so the smaller of two lows is the better one.
+    #[must_use]
+    pub fn evaluate_low_8(cards: &[Card]) -> Option<EvaluatedHand> {
+        let mut ranks: Vec<u8> = cards
+            .iter()
+            .map(|c| if c.rank == 14 { 1 } else { c.rank })
+            .filter(|&r| r <= 8)
+            .collect();
+        ranks.sort_unstable();
+        ranks.dedup();
+        if ranks.len() < 5 {
+            return None;
         }
+        ranks.truncate(5);
+        ranks.reverse();
+        Some(EvaluatedHand::new(HandRank::HighCard, ranks, Vec::new()))
+    }
 
-        for player in &mut self.players {
-            player.reset_for_new_hand();
+    /// Four cards to a flush among hole and board cards on the flop or turn.
+    #[must_use]
+    pub fn has_flush_draw(hole_cards: &[Card], community_cards: &[Card]) -> bool {
+        if !(3..5).contains(&community_cards.len()) {
+            return false;
         }
+        let mut suit_counts: HashMap<Suit, usize> = HashMap::new();
+        for card in hole_cards.iter().chain(community_cards) {
+            *suit_counts.entry(card.suit).or_insert(0) += 1;
+        }
+        suit_counts.values().any(|&n| n == 4) && suit_counts.values().all(|&n| n < 5)
+    }
 
-        self.deck = Deck::new();
-        self.deck.shuffle();
+    /// Four consecutive ranks that either end can complete, e.g. 6-7-8-9, on the
+    /// flop or turn.
+    #[must_use]
+    pub fn has_open_ended_straight_draw(hole_cards: &[Card], community_cards: &[Card]) -> bool {
+        Self::straight_draw_ranks(hole_cards, community_cards)
+            .is_some_and(|present| (2..=10).any(|low| (low..low + 4).all(|r| present[r])))
+    }
 
-        self.community_cards.clear();
+    /// Four of the five ranks of a straight that only one rank completes, such as
+    /// 5-6-8-9 or A-2-3-4.
+    #[must_use]
+    pub fn has_gutshot(hole_cards: &[Card], community_cards: &[Card]) -> bool {
+        !Self::has_open_ended_straight_draw(hole_cards, community_cards)
+            && Self::straight_draw_ranks(hole_cards, community_cards).is_some_and(|present| {
+                (1..=10).any(|low| (low..low + 5).filter(|&r| present[r]).count() == 4)
+            })
+    }
 
-        self.deck.burn();
+    /// Unseen cards that would leave the hero with a straight flush (royal
+    /// included) once added to the board, or a better one if they already hold
+    /// one, in suit then rank order.
+    #[must_use]
+    pub fn straight_flush_outs(hole_cards: &[Card], community_cards: &[Card]) -> Vec<Card> {
+        let known: Vec<Card> = hole_cards.iter().chain(community_cards).copied().collect();
+        let unseen = !cards_to_mask(&known) & ((1u64 << 52) - 1);
+        let current = (known.len() >= 5)
+            .then(|| Self::evaluate(hole_cards, community_cards))
+            .filter(|hand| hand.rank >= HandRank::StraightFlush);
+        let mut board = community_cards.to_vec();
+        mask_to_cards(unseen)
+            .into_iter()
+            .filter(|&card| {
+                board.truncate(community_cards.len());
+                board.push(card);
+                let hand = Self::evaluate(hole_cards, &board);
+                hand.rank >= HandRank::StraightFlush
+                    && current.as_ref().is_none_or(|made| hand > *made)
+            })
+            .collect()
+    }
 
-        for player in &mut self.players {
-            if let Some(cards) = self.deck.deal(2) {
-                player.receive_cards(cards);
-            } else {
-                return Err("Failed to deal hole cards");
-            }
-        }
+    /// On the flop or turn, whether the next card can give the hero the best
+    /// possible eight-or-better low when they do not hold it already.
+    #[must_use]
+    pub fn nut_low_draw(hole_cards: &[Card], community_cards: &[Card]) -> bool {
+        Self::draws_to_nuts(hole_cards, community_cards, |cards| {
+            Self::evaluate_low_8(cards).map(std::cmp::Reverse)
+        })
+    }
 
-        self.pot = 0;
+    /// On the flop or turn, whether the next card can give the hero the best
+    /// possible high hand when they do not hold it already.
+    #[must_use]
+    pub fn nut_high_draw(hole_cards: &[Card], community_cards: &[Card]) -> bool {
+        Self::draws_to_nuts(hole_cards, community_cards, |cards| {
+            Some(Self::evaluate(cards, &[]))
+        })
+    }
 
-        self.post_blinds()?;
+    /// Shared by the nut draw checks; `value` scores a hand, higher being better.
+    fn draws_to_nuts<T: Ord>(
+        hole_cards: &[Card],
+        community_cards: &[Card],
+        value: impl Fn(&[Card]) -> Option<T>,
+    ) -> bool {
+        if !(3..5).contains(&community_cards.len()) {
+            return false;
+        }
+        let holds_nuts = |board: &[Card]| {
+            let mut cards: Vec<Card> = hole_cards.iter().chain(board).copied().collect();
+            let Some(hero) = value(&cards) else {
+                return false;
+            };
+            // The hero's own cards are blockers: no opponent can hold them.
+            let others: Vec<Card> = Deck::all_cards()
+                .into_iter()
+                .filter(|c| !board.contains(c) && !hole_cards.contains(c))
+                .collect();
+            for (i, &first) in others.iter().enumerate() {
+                for &second in &others[i + 1..] {
+                    cards.clear();
+                    cards.extend([first, second]);
+                    cards.extend(board);
+                    if value(&cards).is_some_and(|v| v > hero) {
+                        return false;
+                    }
+                }
+            }
+            true
+        };
 
-        self.stage = GameStage::Preflop;
-        self.current_player = (self.dealer_position + 3) % self.players.len();
-        self.to_call = BIG_BLIND_CHIPS;
-        self.pending_action = true;
-        self.update_action_bounds();
+        if holds_nuts(community_cards) {
+            return false;
+        }
+        let mut board = community_cards.to_vec();
+        Deck::new()
+            .cards
+            .into_iter()
+            .filter(|c| !hole_cards.contains(c) && !community_cards.contains(c))
+            .any(|next| {
+                board.truncate(community_cards.len());
+                board.push(next);
+                holds_nuts(&board)
+            })
+    }
 
-        Ok(())
+    /// Ranks present among the cards, with the ace also at index 1, or `None` when
+    /// there is no street to come or a straight is already made.
+    fn straight_draw_ranks(hole_cards: &[Card], community_cards: &[Card]) -> Option<[bool; 15]> {
+        if !(3..5).contains(&community_cards.len()) {
+            return None;
+        }
+        let mut present = [false; 15];
+        for card in hole_cards.iter().chain(community_cards) {
+            present[usize::from(card.rank)] = true;
+        }
+        present[1] = present[14];
+        let made = (1..=10).any(|low| (low..low + 5).all(|r| present[r]));
+        (!made).then_some(present)
     }
 
-    fn post_blinds(&mut self) -> Result<(), &'static str> {
-        let sb_position = (self.dealer_position + 1) % self.players.len();
-        let bb_position = (self.dealer_position + 2) % self.players.len();
+    /// Find a five-card straight in `ranks`. Given `wheel_low`, the ace also plays
+    /// below that rank, so the wheel is A-2-3-4-5 normally and A-6-7-8-9 in short
+    /// deck; with `None` the ace only plays high.
+    fn find_straight(ranks: &[u8], wheel_low: Option<u8>) -> Option<Vec<u8>> {
+        if ranks.len() < 5 {
+            return None;
+        }
 
-        self.players[sb_position].bet(SMALL_BLIND_CHIPS)?;
-        self.players[bb_position].bet(BIG_BLIND_CHIPS)?;
+        let mut sorted_ranks = ranks.to_vec();
+        sorted_ranks.sort_unstable();
+        sorted_ranks.dedup();
+
+        for i in (0..=sorted_ranks.len().saturating_sub(5)).rev() {
+            let window = &sorted_ranks[i..i + 5];
+            if window.windows(2).all(|w| w[1] == w[0] + 1) {
+                return Some(window.iter().rev().copied().collect());
+            }
+        }
 
-        self.pot += SMALL_BLIND_CHIPS + BIG_BLIND_CHIPS;
+        let lowest_rank = wheel_low?;
+        let wheel_top = lowest_rank + 3;
+        if sorted_ranks.contains(&14)
+            && (lowest_rank..=wheel_top).all(|r| sorted_ranks.contains(&r))
+        {
+            return Some((lowest_rank - 1..=wheel_top).rev().collect());
+        }
 
-        Ok(())
+        None
     }
+}
 
-    fn update_action_bounds(&mut self) {
-        if self.players.is_empty() || self.current_player >= self.players.len() {
-            return;
-        }
+/// Combine cards into a bitmask of `Card::to_bit` values.
+#[must_use]
+pub fn cards_to_mask(cards: &[Card]) -> u64 {
+    cards.iter().fold(0, |mask, card| mask | card.to_bit())
+}
 
-        let current_call = self.to_call;
-        let player = &self.players[self.current_player];
+/// The cards whose bits are set in `mask`, in suit then rank order.
+#[must_use]
+pub fn mask_to_cards(mask: u64) -> Vec<Card> {
+    Suit::ALL
+        .into_iter()
+        .flat_map(|suit| (2..=14).map(move |rank| Card::new(rank, suit)))
+        .filter(|card| mask & card.to_bit() != 0)
+        .collect()
+}
 
-        let min_raise = current_call.saturating_mul(2);
-        let player_chips = player.get_chips();
+/// The rank gap between two cards: 0 for a pair, 1 for connectors. An ace
+/// counts high or low, whichever gives the smaller gap.
+#[must_use]
+pub fn card_gap(a: Card, b: Card) -> u8 {
+    let (high, low) = (a.rank.max(b.rank), a.rank.min(b.rank));
+    let gap = high - low;
+    if high == 14 && low < 14 {
+        gap.min(low - 1)
+    } else {
+        gap
+    }
+}
 
-        self.min_bet = if current_call == 0 {
-            BIG_BLIND_CHIPS
-        } else {
-            min_raise
-        };
+/// The shape of a two-card starting hand.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum HoleClass {
+    Pair,
+    Suited,
+    Offsuit,
+}
 
-        self.max_bet = player_chips.min(MAX_BET_DEFAULT.saturating_mul(MAX_BET_MULTIPLIER));
+#[must_use]
+pub fn classify_hole(a: Card, b: Card) -> HoleClass {
+    if a.rank == b.rank {
+        HoleClass::Pair
+    } else if a.suit == b.suit {
+        HoleClass::Suited
+    } else {
+        HoleClass::Offsuit
+    }
+}
 
-        if self.bet_amount < self.min_bet {
-            self.bet_amount = self.min_bet;
+/// The canonical starting-hand label, high card first: "AKs", "72o", "TT".
+#[must_use]
+pub fn hole_label(a: Card, b: Card) -> String {
+    let (high, low) = (a.rank.max(b.rank), a.rank.min(b.rank));
+    let mut label: String = [rank_char(high), rank_char(low)].into_iter().collect();
+    match classify_hole(a, b) {
+        HoleClass::Pair => {}
+        HoleClass::Suited => label.push('s'),
+        HoleClass::Offsuit => label.push('o'),
+    }
+    label
+}
+
+/// Starting-hand classes as `(high rank, low rank, class)`.
+pub type HandRange = HashSet<(u8, u8, HoleClass)>;
+
+/// Expand a comma-separated range such as "99+, AQs+, KJo, AK" into its
+/// starting-hand classes. "+" raises a pair up to aces and a non-pair's low
+/// card up to one below its high card; no suffix means suited and offsuit.
+/// Entries that do not parse are skipped.
+#[must_use]
+pub fn parse_range(spec: &str) -> HandRange {
+    let mut range = HandRange::new();
+    for entry in spec.split(',').map(str::trim) {
+        let (entry, plus) = match entry.strip_suffix('+') {
+            Some(rest) => (rest, true),
+            None => (entry, false),
+        };
+        let mut chars = entry.chars();
+        let (Some(first), Some(second)) = (
+            chars.next().and_then(rank_from_char),
+            chars.next().and_then(rank_from_char),
+        ) else {
+            continue;
+        };
+        let classes: &[HoleClass] = match chars.next() {
+            None if first == second => &[HoleClass::Pair],
+            None => &[HoleClass::Suited, HoleClass::Offsuit],
+            Some('s') if first != second => &[HoleClass::Suited],
+            Some('o') if first != second => &[HoleClass::Offsuit],
+            _ => continue,
+        };
+        if chars.next().is_some() {
+            continue;
         }
-        if self.bet_amount > self.max_bet {
-            self.bet_amount = self.max_bet;
+
+        let (high, low) = (first.max(second), first.min(second));
+        if high == low {
+            let top = if plus { 14 } else { high };
+            range.extend((high..=top).map(|r| (r, r, HoleClass::Pair)));
+        } else {
+            let top = if plus { high - 1 } else { low };
+            for kicker in low..=top {
+                range.extend(classes.iter().map(|&class| (high, kicker, class)));
+            }
         }
     }
+    range
+}
 
-    /// Process a player's action (fold, check, call, bet, raise, all-in).
-    ///
-    /// # Errors
-    ///
-    /// Returns various errors based on the action type and game state.
-    pub fn perform_action(&mut self, action: PlayerAction) -> Result<String, &'static str> {
-        if !self.pending_action {
-            return Err("No pending action");
-        }
+/// Every two-card starting hand, each of the 1326 combinations once.
+pub fn all_starting_hands() -> impl Iterator<Item = (Card, Card)> {
+    let cards = Deck::all_cards();
+    (0..52).flat_map(move |i| (i + 1..52).map(move |j| (cards[i], cards[j])))
+}
 
-        let player_idx = self.current_player;
-        let player_name = self.players[player_idx].get_name().to_string();
-        let player = &self.players[player_idx];
+/// The 169 strategically distinct starting hands as `(high rank, low rank, class)`,
+/// the same keys a `HandRange` holds: 13 pairs, 78 suited and 78 offsuit hands.
+pub fn canonical_starting_hands() -> impl Iterator<Item = (u8, u8, HoleClass)> {
+    (2..=14u8).rev().flat_map(|high| {
+        (2..=high).rev().flat_map(move |low| {
+            let classes: &[HoleClass] = if high == low {
+                &[HoleClass::Pair]
+            } else {
+                &[HoleClass::Suited, HoleClass::Offsuit]
+            };
+            classes.iter().map(move |&class| (high, low, class))
+        })
+    })
+}
 
-        if player.is_folded() || player.is_all_in() {
-            return Err("Player cannot act");
-        }
+/// Whether the hole cards `a` and `b` fall in one of `range`'s classes.
+#[must_use]
+pub fn range_contains(range: &HandRange, a: Card, b: Card) -> bool {
+    range.contains(&hole_key(a, b))
+}
 
-        let current_bet = player.get_current_bet();
-        let call_amount = self.to_call.saturating_sub(current_bet);
+fn hole_key(a: Card, b: Card) -> (u8, u8, HoleClass) {
+    (a.rank.max(b.rank), a.rank.min(b.rank), classify_hole(a, b))
+}
 
-        let message = match action {
-            PlayerAction::Fold => {
-                self.players[player_idx].set_folded(true);
-                format!("{player_name} folded")
-            }
+fn rank_from_char(c: char) -> Option<u8> {
+    match c {
+        'A' => Some(14),
+        'K' => Some(13),
+        'Q' => Some(12),
+        'J' => Some(11),
+        'T' => Some(10),
+        '2'..='9' => Some(c as u8 - b'0'),
+        _ => None,
+    }
+}
 
-            PlayerAction::Check => {
-                if call_amount > 0 {
-                    return Err("Cannot check when a bet is pending");
+/// The single-character rank used in hand labels, with 'T' for ten.
+fn rank_char(rank: u8) -> char {
+    match rank {
+        14 => 'A',
+        13 => 'K',
+        12 => 'Q',
+        11 => 'J',
+        10 => 'T',
+        n => char::from(b'0' + n),
+    }
+}
+
+/// A cache key for `(hole, community)` that is the same for hands differing
+/// only by a relabelling of suits, so AhKh and AsKs share a key preflop. The
+/// order of cards within `hole` and within `community` does not matter.
+///
+/// The canonical cards are packed six bits each, up to four hole cards in the
+/// low 24 bits and up to five board cards above them, so the key is stable
+/// across runs and never collides for Hold'em or Omaha hands.
+#[must_use]
+pub fn hand_key(hole: &[Card], community: &[Card]) -> u64 {
+    debug_assert!(hole.len() <= 4 && community.len() <= 5);
+    let relabel = |cards: &[Card], perm: &[usize; 4]| {
+        cards.iter().fold(0u64, |mask, card| {
+            mask | Card::new(card.rank, Suit::ALL[perm[card.suit as usize]]).to_bit()
+        })
+    };
+    // Each card becomes its bit index plus one, so an empty slot stays zero.
+    let pack = |mask: u64| {
+        (0..52u64)
+            .filter(|bit| mask & (1 << bit) != 0)
+            .enumerate()
+            .fold(0u64, |key, (slot, bit)| key | ((bit + 1) << (6 * slot)))
+    };
+    let (hole, board) = SUIT_PERMUTATIONS
+        .iter()
+        .map(|perm| (relabel(hole, perm), relabel(community, perm)))
+        .min()
+        .unwrap_or_default();
+    pack(hole) | (pack(board) << 24)
+}
+
+/// All 24 ways of mapping the four suits onto each other.
+const SUIT_PERMUTATIONS: [[usize; 4]; 24] = suit_permutations();
+
+const fn suit_permutations() -> [[usize; 4]; 24] {
+    let mut perms = [[0; 4]; 24];
+    let mut n = 0;
+    let mut a = 0;
+    while a < 4 {
+        let mut b = 0;
+        while b < 4 {
+            let mut c = 0;
+            while c < 4 {
+                if a != b && a != c && b != c {
+                    perms[n] = [a, b, c, 6 - a - b - c];
+                    n += 1;
                 }
-                self.players[player_idx].set_has_acted(true);
-                format!("{player_name} checked")
+                c += 1;
             }
+            b += 1;
+        }
+        a += 1;
+    }
+    perms
+}
 
-            PlayerAction::Call => {
-                let actual_call = call_amount.min(player.get_chips());
-                self.players[player_idx].bet(actual_call)?;
-                self.pot += actual_call;
-                self.players[player_idx].set_has_acted(true);
-                format!("{player_name} called {actual_call}")
-            }
+/// Monte Carlo equity of the first hand in each heads-up Hold'em matchup over
+/// `iters` random completions of `board`, counting ties as half. One RNG and one
+/// scratch deck are reused across matchups; with the `rayon` feature matchups
+/// run in parallel, each worker keeping its own. A matchup that is not two
+/// hole cards each on at most five board cards gets 0.
+#[must_use]
+pub fn batch_equity(matchups: &[(Vec<Card>, Vec<Card>)], board: &[Card], iters: usize) -> Vec<f64> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        matchups
+            .par_iter()
+            .map_init(
+                || (StdRng::from_entropy(), Vec::with_capacity(52)),
+                |(rng, scratch), (hero, villain)| {
+                    matchup_equity(hero, villain, board, iters, rng, scratch)
+                },
+            )
+            .collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        let mut rng = StdRng::from_entropy();
+        let mut scratch = Vec::with_capacity(52);
+        matchups
+            .iter()
+            .map(|(hero, villain)| {
+                matchup_equity(hero, villain, board, iters, &mut rng, &mut scratch)
+            })
+            .collect()
+    }
+}
+
+/// Monte Carlo Hold'em equity of `hero` against a villain holding any hand in
+/// `villain_range`, counting ties as half. Each iteration draws one of the
+/// range's combos not blocked by `hero` or `community` uniformly, then runs out
+/// the board. Returns 0 for malformed input or a range with no live combos.
+#[must_use]
+pub fn equity_vs_range(
+    hero: &[Card],
+    villain_range: &HandRange,
+    community: &[Card],
+    iters: usize,
+) -> f64 {
+    let mut live: Vec<Card> = Deck::all_cards()
+        .into_iter()
+        .filter(|c| !hero.contains(c) && !community.contains(c))
+        .collect();
+    let combos: Vec<[Card; 2]> = live
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &a)| live[i + 1..].iter().map(move |&b| [a, b]))
+        .filter(|&[a, b]| range_contains(villain_range, a, b))
+        .collect();
+    if hero.len() != 2 || community.len() > 5 || combos.is_empty() || iters == 0 {
+        return 0.0;
+    }
+
+    // `live` still holds the villain's cards; `runout_equity` skips them.
+    let mut rng = StdRng::from_entropy();
+    let total: f64 = (0..iters)
+        .map(|_| {
+            let villain = combos[rng.gen_range(0..combos.len())];
+            runout_equity(hero, &villain, community, 1, &mut rng, &mut live)
+        })
+        .sum();
+    total / iters as f64
+}
+
+fn matchup_equity<R: Rng + ?Sized>(
+    hero: &[Card],
+    villain: &[Card],
+    board: &[Card],
+    iters: usize,
+    rng: &mut R,
+    remaining: &mut Vec<Card>,
+) -> f64 {
+    if hero.len() != 2 || villain.len() != 2 || board.len() > 5 || iters == 0 {
+        return 0.0;
+    }
+    remaining.clear();
+    remaining.extend(
+        Deck::all_cards()
+            .into_iter()
+            .filter(|c| !hero.contains(c) && !villain.contains(c) && !board.contains(c)),
+    );
+    runout_equity(hero, villain, board, iters, rng, remaining)
+}
 
-            PlayerAction::Bet => {
-                if call_amount > 0 {
-                    return Err("Use Raise action instead of Bet when a bet is pending");
+/// Equity of `hero` over `iters` run-outs of `board` drawn from `live`, which
+/// must not hold `hero` or `board` cards. Any of `villain`'s cards left in
+/// `live` are skipped, so one list can serve many villain hands.
+fn runout_equity<R: Rng + ?Sized>(
+    hero: &[Card],
+    villain: &[Card],
+    board: &[Card],
+    iters: usize,
+    rng: &mut R,
+    live: &mut [Card],
+) -> f64 {
+    let missing = 5 - board.len();
+    let mut hero_cards = [hero[0]; 7];
+    let mut villain_cards = [villain[0]; 7];
+    hero_cards[1] = hero[1];
+    villain_cards[1] = villain[1];
+    hero_cards[2..2 + board.len()].copy_from_slice(board);
+    villain_cards[2..2 + board.len()].copy_from_slice(board);
+
+    let mut points = 0.0;
+    for _ in 0..iters {
+        for i in 0..missing {
+            let j = loop {
+                let j = rng.gen_range(i..live.len());
+                if !villain.contains(&live[j]) {
+                    break j;
                 }
-                let bet_amount = self.bet_amount.min(player.get_chips());
-                self.players[player_idx].bet(bet_amount)?;
-                self.to_call = bet_amount;
-                self.pot += bet_amount;
-                format!("{player_name} bet {bet_amount}")
-            }
+            };
+            live.swap(i, j);
+        }
+        hero_cards[7 - missing..].copy_from_slice(&live[..missing]);
+        villain_cards[7 - missing..].copy_from_slice(&live[..missing]);
+        points += match PokerHandEvaluator::evaluate_fast(&hero_cards)
+            .cmp(&PokerHandEvaluator::evaluate_fast(&villain_cards))
+        {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Less => 0.0,
+        };
+    }
+    points / iters as f64
+}
 
-            PlayerAction::Raise => {
-                let raise_amount = self.bet_amount.min(player.get_chips());
-                let total_bet = current_bet + raise_amount;
-                if total_bet <= self.to_call {
-                    return Err("Raise must be greater than current bet");
+/// How a hand was won: everyone else folded, or the best hand at showdown.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WinReason {
+    Fold,
+    Showdown,
+}
+
+/// Outcome of a finished hand. `amounts[i]` is what `winners[i]` collected over
+/// every pot layer, and `best` is the hand that won the main pot when it went
+/// to showdown.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandResult {
+    pub winners: Vec<usize>,
+    pub amounts: Vec<u64>,
+    pub best: Option<EvaluatedHand>,
+    pub reason: WinReason,
+}
+
+impl HandResult {
+    /// How the hand was won, for display: "uncontested" after a fold, otherwise
+    /// "with" and the winning rank.
+    #[must_use]
+    pub fn how_won(&self) -> String {
+        match (self.reason, &self.best) {
+            (WinReason::Showdown, Some(best)) => format!("with {}", best.rank),
+            _ => "uncontested".to_string(),
+        }
+    }
+
+    /// Credit `amounts[i]` to `winners[i]`, adding to any earlier share.
+    fn add_winnings(&mut self, winners: &[usize], amounts: &[u64]) {
+        for (&winner, &amount) in winners.iter().zip(amounts) {
+            match self.winners.iter().position(|&w| w == winner) {
+                Some(pos) => self.amounts[pos] += amount,
+                None => {
+                    self.winners.push(winner);
+                    self.amounts.push(amount);
                 }
-                self.players[player_idx].bet(raise_amount)?;
-                self.to_call = total_bet;
-                self.pot += raise_amount;
-                format!("{player_name} raised to {total_bet}")
             }
+        }
+    }
+}
 
-            PlayerAction::AllIn => {
-                let all_in_amount = player.get_chips();
-                self.players[player_idx].bet(all_in_amount)?;
-                self.pot += all_in_amount;
-                if current_bet + all_in_amount > self.to_call {
-                    self.to_call = current_bet + all_in_amount;
+/// Something that happened during a hand, recorded in order in
+/// `PokerGameState::event_log`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HandEvent {
+    /// A hand began; `stacks` are the seat stacks before blinds were posted.
+    HandStarted {
+        hand_number: u64,
+        dealer: usize,
+        stacks: Vec<u64>,
+    },
+    BlindPosted {
+        player: usize,
+        amount: u64,
+    },
+    /// A player acted. `amount` is the chips added by this action and `total_bet`
+    /// the player's bet on the street afterwards.
+    Action {
+        player: usize,
+        stage: GameStage,
+        action: PlayerAction,
+        amount: u64,
+        total_bet: u64,
+    },
+    StreetDealt {
+        stage: GameStage,
+        cards: Vec<Card>,
+    },
+    /// The unmatched part of a bet went back to `player` before the pot was awarded.
+    UncalledBetReturned {
+        player: usize,
+        amount: u64,
+    },
+    PotAwarded {
+        player: usize,
+        amount: u64,
+    },
+}
+
+/// Receives game events as they happen; register one with
+/// `PokerGameState::add_observer`. Every method defaults to doing nothing.
+pub trait GameObserver {
+    fn on_hand_start(&mut self, _hand_number: u64) {}
+    /// `amount` is the chips the action put in.
+    fn on_action(&mut self, _player: usize, _action: PlayerAction, _amount: u64) {}
+    fn on_street(&mut self, _stage: GameStage, _cards: &[Card]) {}
+    /// The pot was awarded, at showdown or because everyone else folded.
+    fn on_showdown(&mut self, _result: &HandResult) {}
+}
+
+/// Turns hand events into the text shown to players. Install a custom one with
+/// `PokerGameState::set_narrator` to reword or localize the messages.
+pub trait Narrator {
+    /// Describe `event`; `players` resolves the seats it mentions.
+    fn narrate(&self, event: &HandEvent, players: &[Player]) -> String;
+}
+
+/// Plain English narration, e.g. "Alice raises to 200".
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultNarrator;
+
+impl Narrator for DefaultNarrator {
+    fn narrate(&self, event: &HandEvent, players: &[Player]) -> String {
+        let name = |i: usize| players.get(i).map_or("?", Player::get_name);
+        match event {
+            HandEvent::HandStarted { hand_number, .. } => format!("Hand #{hand_number} started"),
+            HandEvent::BlindPosted { player, amount } => {
+                format!("{} posts a blind of {amount}", name(*player))
+            }
+            HandEvent::Action {
+                player,
+                action,
+                amount,
+                total_bet,
+                ..
+            } => {
+                let player = name(*player);
+                match action {
+                    PlayerAction::Fold => format!("{player} folds"),
+                    PlayerAction::Check => format!("{player} checks"),
+                    PlayerAction::Call => format!("{player} calls {amount}"),
+                    PlayerAction::Bet | PlayerAction::BetTo(_) => format!("{player} bets {amount}"),
+                    PlayerAction::Raise | PlayerAction::RaiseTo(_) => {
+                        format!("{player} raises to {total_bet}")
+                    }
+                    PlayerAction::AllIn => format!("{player} is all-in for {amount}"),
                 }
-                format!("{player_name} went all-in with {all_in_amount}")
             }
-        };
+            HandEvent::StreetDealt { stage, cards } => {
+                let cards: Vec<String> = cards.iter().map(ToString::to_string).collect();
+                format!("{stage:?}: {}", cards.join(" "))
+            }
+            HandEvent::UncalledBetReturned { player, amount } => {
+                format!("Uncalled bet ({amount}) returned to {}", name(*player))
+            }
+            HandEvent::PotAwarded { player, amount } => format!("{} wins {amount}", name(*player)),
+        }
+    }
+}
 
-        self.advance_to_next_player();
+/// One step of the dealing sequence for a hand, as returned by
+/// `PokerGameState::dealing_plan`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DealStep {
+    Burn,
+    HoleCard { player: usize },
+    Community { count: usize },
+}
 
-        Ok(message)
-    }
+/// Inclusive range accepted by `set_bet_amount` for a bet or raise, in chips
+/// added on top of the player's current bet, the unit `Bet` and `Raise` use.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BetSizing {
+    pub min: u64,
+    pub max: u64,
+}
 
-    fn advance_to_next_player(&mut self) {
-        let player_count = self.players.len();
-        let mut attempts = 0;
+/// An action the current player may take; `sizing` is set for Bet and Raise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LegalAction {
+    pub action: PlayerAction,
+    pub sizing: Option<BetSizing>,
+}
 
-        while attempts < player_count {
-            self.current_player = (self.current_player + 1) % player_count;
-            let player = &self.players[self.current_player];
+/// Price of a call relative to the pot. `ratio` is pot-to-call (3.0 means 3:1),
+/// `breakeven_equity` is `call / (pot + call)` and `pct` is that value as a percentage.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PotOdds {
+    pub call: u64,
+    pub pot: u64,
+    pub ratio: f64,
+    pub pct: f64,
+    pub breakeven_equity: f64,
+}
 
-            if player.is_folded() || player.is_all_in() {
-                attempts += 1;
-                continue;
-            }
+/// One layer of the pot and the players who can win it. The main pot comes
+/// first; each all-in player caps the layers they are eligible for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SidePot {
+    pub amount: u64,
+    pub eligible: Vec<usize>,
+}
 
-            break;
-        }
+/// What everyone at the table can see of a player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicPlayer {
+    pub name: String,
+    pub chips: u64,
+    pub current_bet: u64,
+    pub total_invested: u64,
+    pub folded: bool,
+    pub all_in: bool,
+}
 
-        self.check_street_complete();
+/// The game as seen by the player to act, returned by
+/// `PokerGameState::public_snapshot`: no undealt deck and only `hero`'s hole
+/// cards, so it is cheap to clone during search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicState {
+    pub hero: usize,
+    pub hero_cards: Vec<Card>,
+    pub players: Vec<PublicPlayer>,
+    pub community_cards: Vec<Card>,
+    pub pot: u64,
+    pub stage: GameStage,
+    pub dealer_position: usize,
+    pub current_player: usize,
+    pub to_call: u64,
+    pub last_raise_size: u64,
+    pub small_blind: u64,
+    pub big_blind: u64,
+    pub variant: Variant,
+}
+
+/// Callback that may rearrange the deck; see `PokerGameState::pre_deal_hook`.
+pub type DeckHook = Rc<RefCell<dyn FnMut(&mut Deck)>>;
+
+#[derive(Clone)]
+pub struct PokerGameState {
+    pub deck: Deck,
+    pub players: Vec<Player>,
+    pub community_cards: Vec<Card>,
+    pub pot: u64,
+    pub stage: GameStage,
+    pub dealer_position: usize,
+    pub current_player: usize,
+    pub to_call: u64,
+    pub pending_action: bool,
+    pub bet_amount: u64,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub pot_odds: f32,
+    pub variant: Variant,
+    pub last_result: Option<HandResult>,
+    pub seed: u64,
+    pub hand_number: u64,
+    pub small_blind: u64,
+    pub big_blind: u64,
+    /// When set, every hand is dealt from a copy of this deck in its given order
+    /// instead of a freshly shuffled one.
+    pub scripted_deck: Option<Deck>,
+    pub event_log: Vec<HandEvent>,
+    /// Size of the last full bet or raise on this street; a raise must be at
+    /// least this big to reopen betting for players who already acted.
+    pub last_raise_size: u64,
+    pub last_aggressor: Option<usize>,
+    /// Optional cap on raises per street, guarding automated play against
+    /// endless re-raising.
+    pub max_raises_per_street: Option<u32>,
+    pub raises_this_street: u32,
+    /// Deal the rest of the board twice when everyone is all-in; see
+    /// `run_it_twice`.
+    pub run_twice_on_all_in: bool,
+    /// Play hi-lo: every showdown splits each pot layer between the best high hand
+    /// and the best eight-or-better low, as `split_pot_hilo` does.
+    pub hi_lo: bool,
+    /// Burn a card before dealing hole cards and each street. Some home games
+    /// turn this off.
+    pub burn_cards: bool,
+    /// Cards burned before each of the flop, turn and river when `burn_cards` is on.
+    pub burn_per_street: usize,
+    /// Straddle announced for the next hand as `(player, amount)`, applied when it
+    /// is dealt.
+    pub pending_straddle: Option<(usize, u64)>,
+    /// Players who sit out owe a big blind when they return instead of simply
+    /// missing the blinds.
+    pub owe_missed_blinds: bool,
+    /// Smallest chip in play. Bet sizes are rounded down to it and split pots
+    /// are shared out in whole chips, the leftover going to the first winner.
+    pub chip_denomination: u64,
+    /// Called with the deck in `start_new_hand` once it is shuffled and before
+    /// any card is dealt, so a test harness can arrange the cards.
+    pub pre_deal_hook: Option<DeckHook>,
+    /// Where `start_new_hand` prepares the next deck, swapped with `deck` once it
+    /// passes validation so a bad deck changes nothing.
+    spare_deck: Deck,
+    observers: Vec<Rc<RefCell<dyn GameObserver>>>,
+    narrator: Rc<dyn Narrator>,
+}
+
+impl PokerGameState {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_variant(Variant::TexasHoldem)
+    }
+
+    /// Like `new`, but every player starts with `chips` instead of `INITIAL_CHIPS`.
+    #[must_use]
+    pub fn new_with_chips(chips: u64) -> Self {
+        let mut state = Self::new();
+        for player in &mut state.players {
+            player.chips = chips;
+        }
+        state
+    }
+
+    #[must_use]
+    pub fn with_variant(variant: Variant) -> Self {
+        Self::seeded_with_variant(rand::thread_rng().gen(), variant)
+    }
+
+    /// Create a session whose every shuffle derives from `seed`, so two sessions
+    /// with the same seed and the same actions play identical hands.
+    #[must_use]
+    pub fn new_seeded(seed: u64) -> Self {
+        Self::seeded_with_variant(seed, Variant::TexasHoldem)
+    }
+
+    /// Create a session with the usual players and an ordered, unshuffled deck,
+    /// for callers that supply their own deck or leave shuffling to
+    /// `start_new_hand`.
+    #[must_use]
+    pub fn empty() -> Self {
+        Self::unshuffled(rand::thread_rng().gen(), Variant::TexasHoldem)
+    }
+
+    fn seeded_with_variant(seed: u64, variant: Variant) -> Self {
+        let mut state = Self::unshuffled(seed, variant);
+        state
+            .deck
+            .shuffle_with_rng(&mut StdRng::seed_from_u64(seed));
+        state
+    }
+
+    fn unshuffled(seed: u64, variant: Variant) -> Self {
+        let deck = Deck::for_variant(variant);
+
+        let mut players = Vec::with_capacity(NUM_PLAYERS);
+        players.push(Player::new("Alice".to_string(), INITIAL_CHIPS));
+        players.push(Player::new("Bob".to_string(), INITIAL_CHIPS));
+
+        PokerGameState {
+            spare_deck: deck.clone(),
+            deck,
+            players,
+            community_cards: Vec::with_capacity(5),
+            pot: 0,
+            stage: GameStage::WaitingToStart,
+            dealer_position: 0,
+            current_player: 0,
+            to_call: 0,
+            pending_action: false,
+            bet_amount: CALL_AMOUNT_DEFAULT,
+            min_bet: MIN_BET_DEFAULT,
+            max_bet: MAX_BET_DEFAULT,
+            pot_odds: 0.0,
+            variant,
+            last_result: None,
+            seed,
+            hand_number: 0,
+            small_blind: SMALL_BLIND_CHIPS,
+            big_blind: BIG_BLIND_CHIPS,
+            scripted_deck: None,
+            event_log: Vec::new(),
+            last_raise_size: BIG_BLIND_CHIPS,
+            last_aggressor: None,
+            max_raises_per_street: None,
+            raises_this_street: 0,
+            run_twice_on_all_in: false,
+            hi_lo: false,
+            burn_cards: true,
+            burn_per_street: 1,
+            pending_straddle: None,
+            owe_missed_blinds: false,
+            chip_denomination: 1,
+            pre_deal_hook: None,
+            observers: Vec::new(),
+            narrator: Rc::new(DefaultNarrator),
+        }
+    }
+
+    fn hand_rng(&self, hand_number: u64) -> StdRng {
+        StdRng::seed_from_u64(self.seed ^ hand_number.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    /// Start a new hand, dealing cards to all players.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err("Not enough players with sufficient chips")` if fewer than 2 players
+    /// have at least MIN_CHIPS_TO_CONTINUE chips, an error if the prepared deck is
+    /// not complete for the variant, or `Err("Deck exhausted")` if it cannot deal
+    /// every seat's hole cards. The state is unchanged on error.
+    pub fn start_new_hand(&mut self) -> Result<(), &'static str> {
+        if self
+            .players
+            .iter()
+            .filter(|p| !p.is_sitting_out() && p.get_chips() >= MIN_CHIPS_TO_CONTINUE)
+            .count()
+            < 2
+        {
+            return Err("Not enough players with sufficient chips");
+        }
+        if let Some((player, _)) = self.pending_straddle {
+            if self.players.get(player).is_none_or(Player::is_sitting_out) {
+                return Err("Player is sitting out");
+            }
+        }
+
+        let hand_number = self.hand_number + 1;
+        let mut deck = std::mem::replace(&mut self.spare_deck, Deck { cards: Vec::new() });
+        if let Some(scripted) = &self.scripted_deck {
+            deck.cards.clone_from(&scripted.cards);
+        } else {
+            deck.reset_for_variant(self.variant);
+            deck.shuffle_with_rng(&mut self.hand_rng(hand_number));
+        }
+        if let Some(hook) = &self.pre_deal_hook {
+            (hook.borrow_mut())(&mut deck);
+        }
+        let hole_card_count = self.variant.hole_card_count();
+        let seats_dealt = self
+            .players
+            .iter()
+            .filter(|p| !p.sitting_out && p.chips > 0)
+            .count();
+        let problem = if !deck.is_complete_for(self.variant) {
+            Some(if self.variant == Variant::ShortDeck {
+                "Deck is not a complete 36-card set"
+            } else {
+                "Deck is not a complete 52-card set"
+            })
+        } else if deck.len() < usize::from(self.burn_cards) + seats_dealt * hole_card_count {
+            Some("Deck exhausted")
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            self.spare_deck = deck;
+            return Err(problem);
+        }
+
+        for player in &mut self.players {
+            player.reset_for_new_hand();
+            // A busted seat sits the hand out like one that chose to.
+            if player.sitting_out || player.chips == 0 {
+                player.folded = true;
+            }
+            if player.sitting_out {
+                player.owes_blind |= self.owe_missed_blinds;
+            }
+        }
+
+        self.hand_number = hand_number;
+        self.spare_deck = std::mem::replace(&mut self.deck, deck);
+
+        self.community_cards.clear();
+        self.last_result = None;
+        self.event_log.clear();
+        self.event_log.push(HandEvent::HandStarted {
+            hand_number: self.hand_number,
+            dealer: self.dealer_position,
+            stacks: self.players.iter().map(Player::get_chips).collect(),
+        });
+        self.notify(|o| o.on_hand_start(self.hand_number));
+
+        if self.burn_cards {
+            self.deck.burn();
+        }
+
+        for player in self.players.iter_mut().filter(|p| !p.folded) {
+            if let Some(cards) = self.deck.deal(hole_card_count) {
+                player.receive_cards(cards);
+            }
+        }
+
+        self.pot = 0;
+
+        self.post_blinds()?;
+
+        self.stage = GameStage::Preflop;
+        let bb_position = self.next_seat_in_hand(self.next_seat_in_hand(self.dealer_position));
+        self.current_player = self.next_to_act_after(bb_position);
+        self.to_call = self.big_blind;
+        self.last_raise_size = self.big_blind;
+        if let Some((player, amount)) = self.pending_straddle.take() {
+            self.apply_straddle(player, amount)?;
+        }
+        self.last_aggressor = None;
+        self.raises_this_street = 0;
+        self.pending_action = true;
+        self.reset_bet_amount();
+        self.play_auto_action()
+    }
+
+    /// Add chips to a player's stack between hands, as in a cash game.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a hand is in progress, the player does not exist, or the
+    /// amount is zero.
+    pub fn rebuy(&mut self, player_idx: usize, amount: u64) -> Result<(), &'static str> {
+        if !matches!(
+            self.stage,
+            GameStage::WaitingToStart | GameStage::HandComplete
+        ) {
+            return Err("Cannot rebuy while a hand is in progress");
+        }
+        if amount == 0 {
+            return Err("Rebuy amount must be positive");
+        }
+        let player = self
+            .players
+            .get_mut(player_idx)
+            .ok_or("Invalid player index")?;
+        player.add_chips(amount);
+        Ok(())
+    }
+
+    /// Change the blinds posted from the next hand on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error while a hand is in progress, if the big blind is zero or
+    /// if the small blind exceeds the big blind.
+    pub fn set_blinds(&mut self, sb: u64, bb: u64) -> Result<(), &'static str> {
+        if !matches!(
+            self.stage,
+            GameStage::WaitingToStart | GameStage::HandComplete
+        ) {
+            return Err("Cannot change blinds while a hand is in progress");
+        }
+        if bb == 0 {
+            return Err("Big blind must be positive");
+        }
+        if sb > bb {
+            return Err("Small blind cannot exceed big blind");
+        }
+        self.small_blind = sb;
+        self.big_blind = bb;
+        Ok(())
+    }
+
+    /// The order in which cards leave the deck over a full hand: one burn, every
+    /// hole card for each seat in turn starting from seat 0, then `burn_per_street`
+    /// burns before the flop, turn and river. Burns are left out when `burn_cards`
+    /// is off.
+    #[must_use]
+    pub fn dealing_plan(&self) -> Vec<DealStep> {
+        let hole_card_count = self.variant.hole_card_count();
+        let mut plan = vec![DealStep::Burn];
+        for player in (0..self.players.len())
+            .filter(|&i| !self.players[i].is_sitting_out() && self.players[i].get_chips() > 0)
+        {
+            plan.extend(std::iter::repeat_n(
+                DealStep::HoleCard { player },
+                hole_card_count,
+            ));
+        }
+        for count in [3, 1, 1] {
+            plan.extend(std::iter::repeat_n(DealStep::Burn, self.burn_per_street));
+            plan.push(DealStep::Community { count });
+        }
+        if !self.burn_cards {
+            plan.retain(|&step| step != DealStep::Burn);
+        }
+        plan
+    }
+
+    /// The first seat after `seat` that was dealt into the current hand. Only
+    /// meaningful while the hand starts, when the seats left out are the only
+    /// ones folded.
+    fn next_seat_in_hand(&self, seat: usize) -> usize {
+        let n = self.players.len();
+        (1..=n)
+            .map(|k| (seat + k) % n)
+            .find(|&i| !self.players[i].is_folded())
+            .unwrap_or((seat + 1) % n)
+    }
+
+    /// The first seat after `seat` whose player can still act.
+    fn next_to_act_after(&self, seat: usize) -> usize {
+        let n = self.players.len();
+        (1..=n)
+            .map(|k| (seat + k) % n)
+            .find(|&i| !self.players[i].is_folded() && !self.players[i].is_all_in())
+            .unwrap_or((seat + 1) % n)
+    }
+
+    fn post_blinds(&mut self) -> Result<(), &'static str> {
+        let sb_position = self.next_seat_in_hand(self.dealer_position);
+        let bb_position = self.next_seat_in_hand(sb_position);
+
+        // A stack shorter than its blind posts what it has and is all-in.
+        for (position, blind) in [
+            (sb_position, self.small_blind),
+            (bb_position, self.big_blind),
+        ] {
+            let player = &mut self.players[position];
+            let amount = player.bet(blind.min(player.get_chips()))?;
+            // Posting a blind is not a voluntary action; the big blind keeps its option.
+            player.set_has_acted(false);
+            self.pot += amount;
+            self.event_log.push(HandEvent::BlindPosted {
+                player: position,
+                amount,
+            });
+        }
+
+        for idx in 0..self.players.len() {
+            let player = &mut self.players[idx];
+            if !player.owes_blind || player.folded {
+                continue;
+            }
+            player.owes_blind = false;
+            if idx == sb_position || idx == bb_position {
+                continue;
+            }
+            let amount = self.big_blind.min(player.get_chips());
+            player.bet(amount)?;
+            player.set_has_acted(false);
+            self.pot += amount;
+            self.event_log.push(HandEvent::BlindPosted {
+                player: idx,
+                amount,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Announce a straddle: `player_idx` posts a blind of `amount` in total when
+    /// the next hand is dealt, which becomes the amount to call, and action starts
+    /// with the seat after them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error once cards are dealt, for an unknown or sitting-out seat,
+    /// or when the amount does not exceed the big blind or the player's stack.
+    pub fn post_straddle(&mut self, player_idx: usize, amount: u64) -> Result<(), &'static str> {
+        if !matches!(
+            self.stage,
+            GameStage::WaitingToStart | GameStage::HandComplete
+        ) {
+            return Err("Straddle only allowed before the deal");
+        }
+        let player = self.players.get(player_idx).ok_or("Invalid player")?;
+        if player.is_sitting_out() {
+            return Err("Player is sitting out");
+        }
+        if amount <= self.big_blind {
+            return Err("Straddle must exceed the big blind");
+        }
+        if amount > player.get_chips() {
+            return Err("Insufficient chips");
+        }
+        self.pending_straddle = Some((player_idx, amount));
+        Ok(())
+    }
+
+    fn apply_straddle(&mut self, player_idx: usize, amount: u64) -> Result<(), &'static str> {
+        let player = &mut self.players[player_idx];
+        if player.is_sitting_out() {
+            return Err("Player is sitting out");
+        }
+        let added = amount.saturating_sub(player.get_current_bet());
+        player.bet(added)?;
+        player.set_has_acted(false);
+        self.pot += added;
+        self.event_log.push(HandEvent::BlindPosted {
+            player: player_idx,
+            amount: added,
+        });
+
+        self.to_call = amount;
+        self.last_raise_size = amount;
+        self.current_player = self.next_to_act_after(player_idx);
+        Ok(())
+    }
+
+    /// Register an observer to be told about hands, actions, streets and
+    /// results. Clones of this state share their observers.
+    pub fn add_observer(&mut self, observer: Rc<RefCell<dyn GameObserver>>) {
+        self.observers.push(observer);
+    }
+
+    /// Replace the `DefaultNarrator` used for the messages `perform_action`
+    /// returns and for `narrate`.
+    pub fn set_narrator(&mut self, narrator: Rc<dyn Narrator>) {
+        self.narrator = narrator;
+    }
+
+    /// Describe `event` with the installed narrator.
+    #[must_use]
+    pub fn narrate(&self, event: &HandEvent) -> String {
+        self.narrator.narrate(event, &self.players)
+    }
+
+    fn notify(&self, mut event: impl FnMut(&mut dyn GameObserver)) {
+        for observer in &self.observers {
+            event(&mut *observer.borrow_mut());
+        }
+    }
+
+    fn update_action_bounds(&mut self) {
+        if self.players.is_empty() || self.current_player >= self.players.len() {
+            return;
+        }
+
+        let current_call = self.to_call;
+        let player = &self.players[self.current_player];
+
+        let min_raise = self
+            .min_raise_total()
+            .saturating_sub(player.get_current_bet());
+        let player_chips = player.get_chips();
+
+        self.max_bet = player_chips.min(MAX_BET_DEFAULT.saturating_mul(MAX_BET_MULTIPLIER));
+
+        // Both bounds count chips added to the current bet. A stack too short for
+        // a full bet can still bet everything it has.
+        self.min_bet = if current_call == 0 {
+            self.big_blind
+        } else {
+            min_raise
+        }
+        .min(self.max_bet);
+
+        if self.bet_amount < self.min_bet {
+            self.bet_amount = self.min_bet;
+        }
+        if self.bet_amount > self.max_bet {
+            self.bet_amount = self.max_bet;
+        }
+    }
+
+    /// Start a new street with the smallest legal bet or raise as the default
+    /// size, so a size chosen on an earlier street never carries over.
+    fn reset_bet_amount(&mut self) {
+        self.update_action_bounds();
+        self.bet_amount = self.min_bet.min(self.max_bet);
+    }
+
+    /// Whether `action` would be accepted by `perform_action` right now.
+    #[must_use]
+    pub fn is_action_legal(&self, action: PlayerAction) -> bool {
+        self.validate_action(action).is_ok()
+    }
+
+    /// Stages in which players bet and act.
+    const BETTING_STREETS: [GameStage; 4] = [
+        GameStage::Preflop,
+        GameStage::Flop,
+        GameStage::Turn,
+        GameStage::River,
+    ];
+
+    fn assert_stage_in(&self, stages: &[GameStage]) -> Result<(), &'static str> {
+        if stages.contains(&self.stage) {
+            Ok(())
+        } else {
+            Err("Wrong stage")
+        }
+    }
+
+    fn validate_action(&self, action: PlayerAction) -> Result<(), &'static str> {
+        self.assert_stage_in(&Self::BETTING_STREETS)?;
+        if !self.pending_action {
+            return Err("No pending action");
+        }
+        let player = self
+            .players
+            .get(self.current_player)
+            .ok_or("Player cannot act")?;
+        if player.is_folded() || player.is_all_in() {
+            return Err("Player cannot act");
+        }
+
+        let current_bet = player.get_current_bet();
+        let call_amount = self.owed_by(player);
+        let raise_chips = self
+            .sized_amount(action, current_bet)
+            .min(player.get_chips());
+        match action {
+            PlayerAction::Check if call_amount > 0 => Err("Cannot check when a bet is pending"),
+            PlayerAction::Bet | PlayerAction::BetTo(_) if call_amount > 0 => {
+                Err("Use Raise action instead of Bet when a bet is pending")
+            }
+            PlayerAction::Raise | PlayerAction::RaiseTo(_)
+                if current_bet + raise_chips <= self.to_call =>
+            {
+                Err("Raise must be greater than current bet")
+            }
+            PlayerAction::Raise | PlayerAction::RaiseTo(_) if player.has_acted() => {
+                Err("Betting has not been reopened")
+            }
+            PlayerAction::Raise | PlayerAction::RaiseTo(_) if self.raise_cap_reached() => {
+                Err("Raise cap reached")
+            }
+            PlayerAction::AllIn
+                if player.has_acted() && current_bet + player.get_chips() > self.to_call =>
+            {
+                Err("Betting has not been reopened")
+            }
+            PlayerAction::AllIn
+                if self.to_call > 0
+                    && current_bet + player.get_chips() > self.to_call
+                    && self.raise_cap_reached() =>
+            {
+                Err("Raise cap reached")
+            }
+            PlayerAction::BetTo(total) | PlayerAction::RaiseTo(total)
+                if total > self.max_raise_to() =>
+            {
+                Err("Insufficient chips")
+            }
+            // `min_raise_to` is already capped at the player's all-in total, so
+            // a short all-in target still passes.
+            PlayerAction::BetTo(total) if total < self.min_raise_to() => Err("Bet below minimum"),
+            PlayerAction::RaiseTo(total) if total < self.min_raise_to() => {
+                Err("Raise below minimum")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn raise_cap_reached(&self) -> bool {
+        self.max_raises_per_street
+            .is_some_and(|cap| self.raises_this_street >= cap)
+    }
+
+    /// Chips a bet or raise puts in: the explicit target for `BetTo`/`RaiseTo`,
+    /// otherwise `bet_amount`.
+    fn sized_amount(&self, action: PlayerAction, current_bet: u64) -> u64 {
+        match action {
+            PlayerAction::BetTo(total) | PlayerAction::RaiseTo(total) => {
+                total.saturating_sub(current_bet)
+            }
+            _ => self.bet_amount,
+        }
+    }
+
+    /// Process a player's action (fold, check, call, bet, raise, all-in).
+    ///
+    /// # Errors
+    ///
+    /// Returns various errors based on the action type and game state.
+    pub fn perform_action(&mut self, action: PlayerAction) -> Result<String, &'static str> {
+        self.validate_action(action)?;
+
+        let player_idx = self.current_player;
+        let player = &self.players[player_idx];
+
+        let current_bet = player.get_current_bet();
+        let call_amount = self.owed_by(player);
+        let previous_to_call = self.to_call;
+
+        match action {
+            PlayerAction::Fold => {
+                self.players[player_idx].set_folded(true);
+            }
+
+            PlayerAction::Check => {
+                self.players[player_idx].set_has_acted(true);
+            }
+
+            PlayerAction::Call => {
+                let actual_call = call_amount.min(player.get_chips());
+                self.players[player_idx].bet(actual_call)?;
+                self.pot += actual_call;
+                self.players[player_idx].set_has_acted(true);
+            }
+
+            PlayerAction::Bet | PlayerAction::BetTo(_) => {
+                let bet_amount = self
+                    .sized_amount(action, current_bet)
+                    .min(player.get_chips());
+                self.players[player_idx].bet(bet_amount)?;
+                self.to_call = current_bet + bet_amount;
+                self.pot += bet_amount;
+            }
+
+            PlayerAction::Raise | PlayerAction::RaiseTo(_) => {
+                let raise_amount = self
+                    .sized_amount(action, current_bet)
+                    .min(player.get_chips());
+                let total_bet = current_bet + raise_amount;
+                self.players[player_idx].bet(raise_amount)?;
+                self.to_call = total_bet;
+                self.pot += raise_amount;
+            }
+
+            PlayerAction::AllIn => {
+                let all_in_amount = player.get_chips();
+                self.players[player_idx].bet(all_in_amount)?;
+                self.pot += all_in_amount;
+                if current_bet + all_in_amount > self.to_call {
+                    self.to_call = current_bet + all_in_amount;
+                }
+            }
+        }
+
+        let total_bet = self.players[player_idx].get_current_bet();
+        if previous_to_call > 0 && total_bet > previous_to_call {
+            self.raises_this_street += 1;
+        }
+        if total_bet > previous_to_call && total_bet - previous_to_call >= self.last_raise_size {
+            self.reopen_betting(player_idx, total_bet - previous_to_call);
+        }
+        let event = HandEvent::Action {
+            player: player_idx,
+            stage: self.stage,
+            action,
+            amount: total_bet - current_bet,
+            total_bet,
+        };
+        let message = self.narrate(&event);
+        self.event_log.push(event);
+        self.notify(|o| o.on_action(player_idx, action, total_bet - current_bet));
+
+        self.advance_to_next_player()?;
+        debug_assert_eq!(self.validate(), Ok(()));
+        self.play_auto_action()?;
+
+        Ok(message)
+    }
+
+    /// Check the invariants every reachable state keeps: no card appears twice
+    /// among the hole cards, board and deck, the pot holds exactly what players
+    /// have committed this hand, and a pending action belongs to a player still
+    /// in the hand while betting is open. `perform_action` checks this after every
+    /// action in debug builds.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first invariant found broken.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen = HashSet::new();
+        let cards = self
+            .players
+            .iter()
+            .flat_map(|p| p.get_hole_cards())
+            .chain(&self.community_cards)
+            .chain(&self.deck.cards);
+        for card in cards {
+            if !seen.insert(*card) {
+                return Err(format!("Duplicate card {card}"));
+            }
+        }
+
+        let committed: u64 = self.players.iter().map(Player::get_total_invested).sum();
+        if self.pot != committed {
+            return Err(format!(
+                "Pot is {} but players have committed {committed}",
+                self.pot
+            ));
+        }
+
+        if self.pending_action
+            && Self::BETTING_STREETS.contains(&self.stage)
+            && self
+                .players
+                .get(self.current_player)
+                .is_none_or(Player::is_folded)
+        {
+            return Err(format!(
+                "Action is on seat {}, which is not in the hand",
+                self.current_player
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pre-set `auto` for `player_idx`; it is played the next time they are to act
+    /// this hand, straight away if that is now. Unknown seats are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from playing the action straight away, if any.
+    pub fn set_auto_action(
+        &mut self,
+        player_idx: usize,
+        auto: AutoAction,
+    ) -> Result<(), &'static str> {
+        if let Some(player) = self.players.get_mut(player_idx) {
+            player.set_auto_action(Some(auto));
+            self.play_auto_action()?;
+        }
+        Ok(())
+    }
+
+    fn play_auto_action(&mut self) -> Result<(), &'static str> {
+        if !self.pending_action || !Self::BETTING_STREETS.contains(&self.stage) {
+            return Ok(());
+        }
+        let Some(auto) = self
+            .players
+            .get_mut(self.current_player)
+            .and_then(|p| p.auto_action.take())
+        else {
+            return Ok(());
+        };
+        let call = self.get_call_amount();
+        let action = match auto {
+            AutoAction::CheckFold | AutoAction::CheckCall | AutoAction::CallAny(_) if call == 0 => {
+                PlayerAction::Check
+            }
+            AutoAction::CheckFold => PlayerAction::Fold,
+            AutoAction::CheckCall => PlayerAction::Call,
+            AutoAction::CallAny(limit) if call <= limit => PlayerAction::Call,
+            AutoAction::CallAny(_) => PlayerAction::Fold,
+        };
+        if self.is_action_legal(action) {
+            self.perform_action(action)?;
+        }
+        Ok(())
+    }
+
+    /// A full bet or raise by `aggressor`: everyone else must act again and may
+    /// raise. A short all-in never gets here, so it does not reopen betting.
+    fn reopen_betting(&mut self, aggressor: usize, raise_size: u64) {
+        self.last_raise_size = raise_size;
+        self.last_aggressor = Some(aggressor);
+        for (idx, player) in self.players.iter_mut().enumerate() {
+            if idx != aggressor {
+                player.set_has_acted(false);
+            }
+        }
+    }
+
+    /// Debug aid for reproducing hands: make `cards` the next community cards
+    /// dealt, taking them out of the deck if they are still in it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a card is repeated or already dealt, or the board would
+    /// exceed five cards.
+    #[cfg(debug_assertions)]
+    pub fn force_community(&mut self, cards: &[Card]) -> Result<(), &'static str> {
+        if self.community_cards.len() + cards.len() > 5 {
+            return Err("Board cannot have more than 5 cards");
+        }
+        let dealt = self
+            .community_cards
+            .iter()
+            .chain(self.players.iter().flat_map(|p| p.get_hole_cards()));
+        let mut seen: HashSet<Card> = dealt.copied().collect();
+        if !cards.iter().all(|&card| seen.insert(card)) {
+            return Err("Card already dealt");
+        }
+
+        self.deck.cards.retain(|card| !cards.contains(card));
+        self.deck.cards.splice(0..0, cards.iter().copied());
+        Ok(())
+    }
+
+    /// Fold the current player and, if that ends the hand, deal the next one
+    /// straight away. The dealer button still rotates as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fold is not allowed or the next hand cannot start.
+    pub fn fold_and_next(&mut self) -> Result<(), &'static str> {
+        self.perform_action(PlayerAction::Fold)?;
+        if self.stage == GameStage::HandComplete {
+            self.start_new_hand()?;
+        }
+        Ok(())
+    }
+
+    fn advance_to_next_player(&mut self) -> Result<(), &'static str> {
+        let player_count = self.players.len();
+        let mut attempts = 0;
+
+        while attempts < player_count {
+            self.current_player = (self.current_player + 1) % player_count;
+            let player = &self.players[self.current_player];
+
+            if player.is_folded() || player.is_all_in() {
+                attempts += 1;
+                continue;
+            }
+
+            break;
+        }
+
+        self.update_action_bounds();
+        self.check_street_complete()
+    }
+
+    fn get_active_players(&self) -> Vec<usize> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.is_folded())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn get_betting_players(&self) -> Vec<usize> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.is_folded() && !p.is_all_in())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn check_street_complete(&mut self) -> Result<(), &'static str> {
+        let active_players = self.get_active_players();
+
+        if active_players.len() == 1 {
+            self.determine_winner();
+            return Ok(());
+        }
+
+        // With nobody left to bet against, a player who has matched the bet has
+        // no decision to make, so the board runs out.
+        if self.nobody_can_act() {
+            return self.finish_without_action();
+        }
+
+        let betting_players = self.get_betting_players();
+
+        let all_acted = betting_players.iter().all(|&i| self.players[i].has_acted());
+
+        let bets_equal = betting_players
+            .iter()
+            .all(|&i| self.players[i].get_current_bet() == self.to_call);
+
+        if all_acted && bets_equal {
+            self.advance_street()?;
+        }
+        Ok(())
+    }
+
+    /// Progress a hand in which nobody can act any more because every remaining
+    /// player, or all but one who has already matched the bet, is all-in: the
+    /// board runs out and the pot goes to showdown. Does nothing otherwise.
+    /// `perform_action` already does this after each action; call it when the
+    /// state was changed some other way.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err("Deck exhausted")` if the deck cannot complete the board.
+    pub fn advance_if_no_action(&mut self) -> Result<(), &'static str> {
+        if !matches!(
+            self.stage,
+            GameStage::Preflop | GameStage::Flop | GameStage::Turn | GameStage::River
+        ) || self.get_active_players().len() < 2
+        {
+            return Ok(());
+        }
+        if self.nobody_can_act() {
+            self.finish_without_action()?;
+        }
+        Ok(())
+    }
+
+    /// Every player still in is all-in, or all but one who has matched the bet.
+    fn nobody_can_act(&self) -> bool {
+        match self.get_betting_players().as_slice() {
+            [] => true,
+            &[only] => self.players[only].get_current_bet() >= self.to_call,
+            _ => false,
+        }
+    }
+
+    fn finish_without_action(&mut self) -> Result<(), &'static str> {
+        if self.run_twice_on_all_in {
+            self.run_it_twice()?;
+        } else {
+            self.run_out_board()?;
+            self.determine_winner();
+        }
+        Ok(())
+    }
+
+    fn run_out_board(&mut self) -> Result<(), &'static str> {
+        for player in &mut self.players {
+            player.reset_street_bet();
+        }
+        self.to_call = 0;
+        while self.community_cards.len() < 5 {
+            match self.stage {
+                GameStage::Preflop => {
+                    self.deal_community_cards(3)?;
+                    self.stage = GameStage::Flop;
+                }
+                GameStage::Flop => {
+                    self.deal_community_cards(1)?;
+                    self.stage = GameStage::Turn;
+                }
+                GameStage::Turn => {
+                    self.deal_community_cards(1)?;
+                    self.stage = GameStage::River;
+                }
+                _ => break,
+            }
+        }
+        self.stage = GameStage::Showdown;
+        Ok(())
+    }
+
+    fn advance_street(&mut self) -> Result<(), &'static str> {
+        let next_cards = match self.stage {
+            GameStage::Preflop => 3,
+            GameStage::Flop | GameStage::Turn => 1,
+            _ => 0,
+        };
+        if next_cards > 0 && !self.deck_can_deal(next_cards) {
+            return Err("Deck exhausted");
+        }
+
+        for player in &mut self.players {
+            player.set_has_acted(false);
+            player.reset_street_bet();
+        }
+
+        self.to_call = 0;
+        self.last_raise_size = self.big_blind;
+        self.raises_this_street = 0;
+        if self.stage != GameStage::River {
+            // The river aggressor is kept for the showdown reveal order.
+            self.last_aggressor = None;
+        }
+
+        match self.stage {
+            GameStage::Preflop => {
+                self.deal_community_cards(3)?;
+                self.stage = GameStage::Flop;
+            }
+            GameStage::Flop => {
+                self.deal_community_cards(1)?;
+                self.stage = GameStage::Turn;
+            }
+            GameStage::Turn => {
+                self.deal_community_cards(1)?;
+                self.stage = GameStage::River;
+            }
+            GameStage::River => {
+                self.stage = GameStage::Showdown;
+                self.determine_winner();
+            }
+            _ => {
+                self.end_hand();
+            }
+        }
+
+        self.current_player = self.next_to_act_after(self.dealer_position);
+        self.pending_action = true;
+        self.reset_bet_amount();
+        Ok(())
+    }
+
+    fn street_burns(&self) -> usize {
+        if self.burn_cards {
+            self.burn_per_street
+        } else {
+            0
+        }
+    }
+
+    /// Whether the deck still holds the burns and `count` cards of one street.
+    fn deck_can_deal(&self, count: usize) -> bool {
+        self.deck.len() >= self.street_burns() + count
+    }
+
+    /// Burn and deal `count` board cards, leaving the deck untouched if it is too
+    /// short for that.
+    fn deal_community_cards(&mut self, count: usize) -> Result<(), &'static str> {
+        if !self.deck_can_deal(count) {
+            return Err("Deck exhausted");
+        }
+        if self.burn_cards {
+            for _ in 0..self.burn_per_street {
+                self.deck.burn();
+            }
+        }
+        let cards = self.deck.deal(count).ok_or("Deck exhausted")?;
+        self.community_cards.extend(cards.iter().copied());
+        let stage = match self.community_cards.len() {
+            0..=3 => GameStage::Flop,
+            4 => GameStage::Turn,
+            _ => GameStage::River,
+        };
+        self.notify(|o| o.on_street(stage, &cards));
+        self.event_log.push(HandEvent::StreetDealt { stage, cards });
+        Ok(())
+    }
+
+    fn showdown_winners(&self, active_players: &[usize]) -> (Vec<usize>, Option<EvaluatedHand>) {
+        let mut best_hand: Option<EvaluatedHand> = None;
+        let mut winners: Vec<usize> = Vec::new();
+
+        for &player_idx in active_players {
+            let hand = self.variant.evaluate(
+                self.players[player_idx].get_hole_cards(),
+                &self.community_cards,
+            );
+
+            match best_hand.as_ref() {
+                Some(best) => match self.variant.compare_hands(&hand, best) {
+                    std::cmp::Ordering::Greater => {
+                        best_hand = Some(hand);
+                        winners.clear();
+                        winners.push(player_idx);
+                    }
+                    std::cmp::Ordering::Equal => {
+                        winners.push(player_idx);
+                    }
+                    std::cmp::Ordering::Less => {}
+                },
+                None => {
+                    best_hand = Some(hand);
+                    winners.push(player_idx);
+                }
+            }
+        }
+
+        (winners, best_hand)
+    }
+
+    /// Return whatever the biggest investor put in beyond what anyone else
+    /// matched, so it never ends up in a pot.
+    fn return_uncalled_bet(&mut self) {
+        let Some((top, uncalled)) = self.uncalled_bet() else {
+            return;
+        };
+        self.players[top].refund_uncalled(uncalled);
+        self.pot -= uncalled;
+        self.event_log.push(HandEvent::UncalledBetReturned {
+            player: top,
+            amount: uncalled,
+        });
+    }
+
+    /// The seat whose investment nobody matched and the unmatched part, if any.
+    fn uncalled_bet(&self) -> Option<(usize, u64)> {
+        let top = (0..self.players.len()).max_by_key(|&i| self.players[i].get_total_invested())?;
+        let matched = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != top)
+            .map(|(_, p)| p.get_total_invested())
+            .max()
+            .unwrap_or(0);
+        let uncalled = self.players[top]
+            .get_total_invested()
+            .saturating_sub(matched);
+        (uncalled > 0).then_some((top, uncalled))
+    }
+
+    /// Who would win the hand if it were settled on the current board, and what
+    /// each winner would collect once any uncalled bet is returned. Each layer of
+    /// `side_pots` goes to the best hand among the players eligible for it.
+    /// Changes nothing; `winners` is empty when nobody is left to win.
+    #[must_use]
+    pub fn compute_showdown(&self) -> HandResult {
+        let active_players = self.get_active_players();
+        if active_players.len() == 1 {
+            let pot = self.pot - self.uncalled_bet().map_or(0, |(_, amount)| amount);
+            return HandResult {
+                winners: active_players,
+                amounts: vec![pot],
+                best: None,
+                reason: WinReason::Fold,
+            };
+        }
+        self.settle_layers(&self.side_pots(), self.hi_lo)
+    }
+
+    /// Award each of `pots` to the best hand among its eligible players. With
+    /// `hi_lo`, a layer with a qualifying low is split, the odd chip going high.
+    fn settle_layers(&self, pots: &[SidePot], hi_lo: bool) -> HandResult {
+        let mut result = HandResult {
+            winners: Vec::new(),
+            amounts: Vec::new(),
+            best: None,
+            reason: WinReason::Showdown,
+        };
+        for pot in pots {
+            let (high, best) = self.showdown_winners(&pot.eligible);
+            if result.best.is_none() {
+                result.best = best;
+            }
+            let low = if hi_lo {
+                self.low_winners(&pot.eligible)
+            } else {
+                Vec::new()
+            };
+            // The high half takes any odd chips, as the first share does.
+            let (high_share, low_share) = if low.is_empty() {
+                (pot.amount, 0)
+            } else {
+                let halves = self.shares(2, pot.amount);
+                (halves[0], halves[1])
+            };
+            result.add_winnings(&high, &self.shares(high.len(), high_share));
+            result.add_winnings(&low, &self.shares(low.len(), low_share));
+        }
+        result
+    }
+
+    /// The players among `eligible` holding the best eight-or-better low.
+    fn low_winners(&self, eligible: &[usize]) -> Vec<usize> {
+        let lows: Vec<(usize, EvaluatedHand)> = eligible
+            .iter()
+            .filter_map(|&i| self.low_hand(i).map(|low| (i, low)))
+            .collect();
+        let best_low = lows.iter().map(|(_, low)| low).min();
+        lows.iter()
+            .filter(|(_, low)| Some(low) == best_low)
+            .map(|&(i, _)| i)
+            .collect()
+    }
+
+    /// The player's eight-or-better low on the current board. In Omaha it must
+    /// use exactly two hole cards and three board cards.
+    fn low_hand(&self, player_idx: usize) -> Option<EvaluatedHand> {
+        let hole = self.players[player_idx].get_hole_cards();
+        let board = &self.community_cards;
+        if self.variant != Variant::Omaha {
+            let cards: Vec<Card> = hole.iter().chain(board).copied().collect();
+            return PokerHandEvaluator::evaluate_low_8(&cards);
+        }
+
+        let mut best: Option<EvaluatedHand> = None;
+        for (i, &a) in hole.iter().enumerate() {
+            for &b in &hole[i + 1..] {
+                for x in 0..board.len() {
+                    for y in x + 1..board.len() {
+                        for z in y + 1..board.len() {
+                            let five = [a, b, board[x], board[y], board[z]];
+                            if let Some(low) = PokerHandEvaluator::evaluate_low_8(&five) {
+                                if best.as_ref().is_none_or(|b| low < *b) {
+                                    best = Some(low);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn determine_winner(&mut self) {
+        self.return_uncalled_bet();
+        let result = self.compute_showdown();
+        if result.winners.is_empty() {
+            self.end_hand();
+            return;
+        }
+        self.finish_showdown(result);
+    }
+
+    /// Pay out `result`, report it and end the hand.
+    fn finish_showdown(&mut self, result: HandResult) {
+        self.pay_out(&result.winners, &result.amounts);
+        self.notify(|o| o.on_showdown(&result));
+        self.last_result = Some(result);
+        self.end_hand();
+    }
+
+    /// `amount` split `winners` ways in whole `chip_denomination` chips, the odd
+    /// chips going to the first.
+    fn shares(&self, winners: usize, amount: u64) -> Vec<u64> {
+        if winners == 0 {
+            return Vec::new();
+        }
+        let unit = self.chip_denomination.max(1);
+        let share = amount / winners as u64 / unit * unit;
+        let mut amounts = vec![share; winners];
+        amounts[0] += amount - share * winners as u64;
+        amounts
+    }
+
+    fn pay_out(&mut self, winners: &[usize], amounts: &[u64]) {
+        for (&winner_idx, &share) in winners.iter().zip(amounts) {
+            self.players[winner_idx].collect_pot(share);
+            self.event_log.push(HandEvent::PotAwarded {
+                player: winner_idx,
+                amount: share,
+            });
+        }
+    }
+
+    /// Deal the remaining board twice from the rest of the deck and award half of
+    /// every pot layer on each run-out, ending the hand. Meant for when every
+    /// remaining player is all-in; returns one result per run, or nothing if no
+    /// hand is contested. `last_result` combines both runs, so its amounts add up
+    /// to the whole pot and its `best` is the first run's. `community_cards` is
+    /// left showing the first run's board; both boards are in the event log as
+    /// `StreetDealt` events.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err("Betting is still open")` while a player can still act, and
+    /// `Err("Deck exhausted")` if the deck cannot deal both run-outs, changing
+    /// nothing in either case.
+    pub fn run_it_twice(&mut self) -> Result<Vec<HandResult>, &'static str> {
+        let active_players = self.get_active_players();
+        if active_players.len() < 2
+            || matches!(
+                self.stage,
+                GameStage::WaitingToStart | GameStage::HandComplete
+            )
+        {
+            return Ok(Vec::new());
+        }
+        if !self.nobody_can_act() {
+            return Err("Betting is still open");
+        }
+        let missing = 5usize.saturating_sub(self.community_cards.len());
+        if missing > 0 && self.deck.len() < 2 * (self.street_burns() + missing) {
+            return Err("Deck exhausted");
+        }
+
+        self.return_uncalled_bet();
+        let layers = self.side_pots();
+        let board = self.community_cards.clone();
+        let mut first_board = None;
+        let mut results: Vec<HandResult> = Vec::with_capacity(2);
+        for run in 0..2 {
+            self.community_cards.clone_from(&board);
+            if board.len() < 5 {
+                self.deal_community_cards(5 - board.len())?;
+            }
+            first_board.get_or_insert_with(|| self.community_cards.clone());
+
+            // The first run takes the odd chips of each layer.
+            let halves: Vec<SidePot> = layers
+                .iter()
+                .map(|pot| SidePot {
+                    amount: self.shares(2, pot.amount)[run],
+                    eligible: pot.eligible.clone(),
+                })
+                .collect();
+            let result = self.settle_layers(&halves, self.hi_lo);
+            self.pay_out(&result.winners, &result.amounts);
+            self.notify(|o| o.on_showdown(&result));
+            results.push(result);
+        }
+
+        let mut combined = results[0].clone();
+        combined.add_winnings(&results[1].winners, &results[1].amounts);
+        self.community_cards = first_board.unwrap_or(board);
+        self.last_result = Some(combined);
+        self.end_hand();
+        Ok(results)
+    }
+
+    /// Settle the hand hi-lo now, as a showdown does when `hi_lo` is set: each pot
+    /// layer is split between the best high hand and the best eight-or-better
+    /// low, the odd chip going high. With no qualifying low the high hand takes
+    /// the layer. Does nothing unless a hand is being contested.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err("Hi-lo needs a complete board")` before all five board cards
+    /// are out, and `Err("Betting is still open")` while a player can still act
+    /// outside a showdown.
+    pub fn split_pot_hilo(&mut self) -> Result<(), &'static str> {
+        let active_players = self.get_active_players();
+        if active_players.len() < 2
+            || matches!(
+                self.stage,
+                GameStage::WaitingToStart | GameStage::HandComplete
+            )
+        {
+            return Ok(());
+        }
+        if self.community_cards.len() != 5 {
+            return Err("Hi-lo needs a complete board");
+        }
+        if self.stage != GameStage::Showdown && !self.nobody_can_act() {
+            return Err("Betting is still open");
+        }
+
+        self.return_uncalled_bet();
+        let result = self.settle_layers(&self.side_pots(), true);
+        self.finish_showdown(result);
+        Ok(())
+    }
+
+    fn end_hand(&mut self) {
+        self.stage = GameStage::HandComplete;
+        self.record_player_stats();
+        for player in &mut self.players {
+            player.chip_history.push(player.chips);
+        }
+        self.dealer_position = (self.dealer_position + 1) % self.players.len();
+    }
+
+    fn record_player_stats(&mut self) {
+        let mut vpip = vec![false; self.players.len()];
+        let mut pfr = vec![false; self.players.len()];
+        let mut won = vec![false; self.players.len()];
+        let mut preflop_high = 0;
+
+        for event in &self.event_log {
+            match *event {
+                HandEvent::BlindPosted { amount, .. } => {
+                    preflop_high = preflop_high.max(amount);
+                }
+                HandEvent::Action {
+                    player,
+                    stage: GameStage::Preflop,
+                    action,
+                    total_bet,
+                    ..
+                } => {
+                    let raised = match action {
+                        PlayerAction::Bet
+                        | PlayerAction::Raise
+                        | PlayerAction::BetTo(_)
+                        | PlayerAction::RaiseTo(_) => true,
+                        PlayerAction::AllIn => total_bet > preflop_high,
+                        _ => false,
+                    };
+                    vpip[player] |=
+                        raised || matches!(action, PlayerAction::Call | PlayerAction::AllIn);
+                    pfr[player] |= raised;
+                    preflop_high = preflop_high.max(total_bet);
+                }
+                HandEvent::PotAwarded { player, amount } => {
+                    won[player] = true;
+                    self.players[player].stats.chips_won += amount;
+                }
+                _ => {}
+            }
+        }
+
+        for (i, player) in self.players.iter_mut().enumerate() {
+            if player.hole_cards.is_empty() {
+                continue;
+            }
+            player.stats.hands_played += 1;
+            player.stats.vpip_hands += u64::from(vpip[i]);
+            player.stats.pfr_hands += u64::from(pfr[i]);
+            player.stats.hands_won += u64::from(won[i]);
+        }
+    }
+
+    /// The current hand's event log split into streets. Each `StreetDealt` event
+    /// starts a new group; everything before the flop, including the blinds, is
+    /// `Preflop`, and returned bets and awards stay with the street the hand
+    /// ended on.
+    #[must_use]
+    pub fn history_by_street(&self) -> Vec<(GameStage, Vec<HandEvent>)> {
+        let mut groups: Vec<(GameStage, Vec<HandEvent>)> = Vec::new();
+        for event in &self.event_log {
+            match event {
+                HandEvent::StreetDealt { stage, .. } => groups.push((*stage, Vec::new())),
+                _ if groups.is_empty() => groups.push((GameStage::Preflop, Vec::new())),
+                _ => {}
+            }
+            if let Some((_, events)) = groups.last_mut() {
+                events.push(event.clone());
+            }
+        }
+        groups
+    }
+
+    /// Export the current hand's event log in a PokerStars-style hand-history
+    /// format: header, seats, blinds, each street's actions, showdown and summary.
+    #[must_use]
+    pub fn to_hand_history_text(&self) -> String {
+        let name = |i: usize| self.players.get(i).map_or("?", Player::get_name);
+        let join_cards = |cards: &[Card]| {
+            cards
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let game = match self.variant {
+            Variant::TexasHoldem => "Hold'em No Limit",
+            Variant::Omaha => "Omaha No Limit",
+            Variant::ShortDeck => "Short Deck No Limit",
+        };
+
+        let mut lines = Vec::new();
+        let mut street_high = 0;
+        let mut hole_cards_dealt = false;
+        let mut showdown_listed = false;
+        for event in &self.event_log {
+            match event {
+                HandEvent::HandStarted {
+                    hand_number,
+                    dealer,
+                    stacks,
+                } => {
+                    lines.push(format!(
+                        "Hand #{hand_number}: {game} ({}/{})",
+                        self.small_blind, self.big_blind
+                    ));
+                    lines.push(format!("Seat #{} is the button", dealer + 1));
+                    for (i, chips) in stacks.iter().enumerate() {
+                        lines.push(format!("Seat {}: {} ({chips} in chips)", i + 1, name(i)));
+                    }
+                }
+                &HandEvent::BlindPosted { player, amount } => {
+                    let blind = if amount == self.small_blind && street_high == 0 {
+                        "small"
+                    } else {
+                        "big"
+                    };
+                    lines.push(format!("{}: posts {blind} blind {amount}", name(player)));
+                    street_high = street_high.max(amount);
+                }
+                &HandEvent::Action {
+                    player,
+                    action,
+                    amount,
+                    total_bet,
+                    ..
+                } => {
+                    if !hole_cards_dealt {
+                        lines.push("*** HOLE CARDS ***".to_string());
+                        hole_cards_dealt = true;
+                    }
+                    let all_in = if action == PlayerAction::AllIn {
+                        " and is all-in"
+                    } else {
+                        ""
+                    };
+                    let verb = match action {
+                        PlayerAction::Fold => "folds".to_string(),
+                        PlayerAction::Check => "checks".to_string(),
+                        _ if total_bet <= street_high => format!("calls {amount}"),
+                        _ if street_high == 0 => format!("bets {amount}"),
+                        _ => format!("raises {} to {total_bet}", total_bet - street_high),
+                    };
+                    lines.push(format!("{}: {verb}{all_in}", name(player)));
+                    street_high = street_high.max(total_bet);
+                }
+                HandEvent::StreetDealt { stage, cards } => {
+                    if !hole_cards_dealt {
+                        lines.push("*** HOLE CARDS ***".to_string());
+                        hole_cards_dealt = true;
+                    }
+                    let street = match stage {
+                        GameStage::Flop => "FLOP",
+                        GameStage::Turn => "TURN",
+                        _ => "RIVER",
+                    };
+                    lines.push(format!("*** {street} *** [{}]", join_cards(cards)));
+                    street_high = 0;
+                }
+                &HandEvent::UncalledBetReturned { player, amount } => {
+                    lines.push(format!(
+                        "Uncalled bet ({amount}) returned to {}",
+                        name(player)
+                    ));
+                }
+                &HandEvent::PotAwarded { player, amount } => {
+                    if self
+                        .last_result
+                        .as_ref()
+                        .is_some_and(|r| r.reason == WinReason::Showdown)
+                        && !showdown_listed
+                    {
+                        lines.push("*** SHOWDOWN ***".to_string());
+                        showdown_listed = true;
+                        for (i, cards) in self.revealed_hands() {
+                            lines.push(format!("{}: shows [{}]", name(i), join_cards(&cards)));
+                        }
+                    }
+                    lines.push(format!("{} collected {amount} from pot", name(player)));
+                }
+            }
+        }
+
+        if self.stage == GameStage::HandComplete {
+            lines.push("*** SUMMARY ***".to_string());
+            lines.push(format!("Total pot {}", self.pot));
+            if !self.community_cards.is_empty() {
+                lines.push(format!("Board [{}]", join_cards(&self.community_cards)));
+            }
+            if let Some(result) = &self.last_result {
+                for (&winner, amount) in result.winners.iter().zip(&result.amounts) {
+                    lines.push(format!(
+                        "Seat {}: {} collected ({amount})",
+                        winner + 1,
+                        name(winner)
+                    ));
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    #[must_use]
+    pub fn get_stage_string(&self) -> &'static str {
+        match self.stage {
+            GameStage::Preflop => "Preflop",
+            GameStage::Flop => "Flop",
+            GameStage::Turn => "Turn",
+            GameStage::River => "River",
+            GameStage::Showdown => "Showdown",
+            GameStage::HandComplete => "Complete",
+            GameStage::WaitingToStart => "Waiting",
+        }
+    }
+
+    /// A lightweight view for the current player, leaving out the deck and the
+    /// other players' hole cards.
+    #[must_use]
+    pub fn public_snapshot(&self) -> PublicState {
+        let hero = self.current_player;
+        PublicState {
+            hero,
+            hero_cards: self
+                .players
+                .get(hero)
+                .map(|p| p.get_hole_cards().to_vec())
+                .unwrap_or_default(),
+            players: self
+                .players
+                .iter()
+                .map(|p| PublicPlayer {
+                    name: p.get_name().to_string(),
+                    chips: p.get_chips(),
+                    current_bet: p.get_current_bet(),
+                    total_invested: p.get_total_invested(),
+                    folded: p.is_folded(),
+                    all_in: p.is_all_in(),
+                })
+                .collect(),
+            community_cards: self.community_cards.clone(),
+            pot: self.pot,
+            stage: self.stage,
+            dealer_position: self.dealer_position,
+            current_player: self.current_player,
+            to_call: self.to_call,
+            last_raise_size: self.last_raise_size,
+            small_blind: self.small_blind,
+            big_blind: self.big_blind,
+            variant: self.variant,
+        }
+    }
+
+    /// Index of the betting round: 0 preflop (and before the first deal), 1 flop,
+    /// 2 turn, 3 river and 4 once betting is over.
+    #[must_use]
+    pub fn betting_round(&self) -> u8 {
+        match self.stage {
+            GameStage::WaitingToStart | GameStage::Preflop => 0,
+            GameStage::Flop => 1,
+            GameStage::Turn => 2,
+            GameStage::River => 3,
+            GameStage::Showdown | GameStage::HandComplete => 4,
+        }
+    }
+
+    /// Whether more bets or raises can go in on this street: a betting round is
+    /// under way, the raise cap is not reached and two players still have chips.
+    #[must_use]
+    pub fn is_betting_open(&self) -> bool {
+        matches!(
+            self.stage,
+            GameStage::Preflop | GameStage::Flop | GameStage::Turn | GameStage::River
+        ) && !self.raise_cap_reached()
+            && self.get_betting_players().len() >= 2
+    }
+
+    /// Set the pending bet size, clamped into `[min_bet, max_bet]`. Returns the
+    /// amount actually stored so callers can resync their controls.
+    pub fn set_bet_amount(&mut self, amount: u64) -> u64 {
+        self.update_action_bounds();
+        self.bet_amount = self.clamp_bet(amount);
+        self.bet_amount
+    }
+
+    /// A bet of `fraction` of the pot (1.0 for pot-sized), clamped into
+    /// `[min_bet, max_bet]`.
+    #[must_use]
+    pub fn bet_fraction(&self, fraction: f64) -> u64 {
+        self.clamp_bet((self.pot as f64 * fraction).round() as u64)
+    }
+
+    /// A bet of `multiple` big blinds, clamped into `[min_bet, max_bet]`.
+    #[must_use]
+    pub fn bet_bb(&self, multiple: f64) -> u64 {
+        self.clamp_bet((self.big_blind as f64 * multiple).round() as u64)
+    }
+
+    fn clamp_bet(&self, amount: u64) -> u64 {
+        let unit = self.chip_denomination.max(1);
+        (amount / unit * unit).max(self.min_bet).min(self.max_bet)
+    }
+
+    #[must_use]
+    pub fn is_pending_action(&self) -> bool {
+        self.pending_action
+    }
+
+    #[must_use]
+    pub fn get_current_player(&self) -> usize {
+        self.current_player
+    }
+
+    /// Every player's hole cards for a spectator view. Empty until the hand reaches
+    /// showdown so cards are never leaked mid-hand.
+    #[must_use]
+    pub fn reveal_all(&self) -> Vec<(usize, Vec<Card>)> {
+        if !matches!(self.stage, GameStage::Showdown | GameStage::HandComplete) {
+            return Vec::new();
+        }
+        self.players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i, p.get_hole_cards().to_vec()))
+            .collect()
+    }
+
+    /// Hands shown at the table after a showdown: every player who reached it,
+    /// except losers who auto-muck. Empty when the pot was won without a showdown.
+    #[must_use]
+    pub fn revealed_hands(&self) -> Vec<(usize, Vec<Card>)> {
+        let Some(result) = self
+            .last_result
+            .as_ref()
+            .filter(|r| r.reason == WinReason::Showdown)
+        else {
+            return Vec::new();
+        };
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| !p.is_folded() && (!p.is_auto_muck() || result.winners.contains(i)))
+            .map(|(i, p)| (i, p.get_hole_cards().to_vec()))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn player_index_by_name(&self, name: &str) -> Option<usize> {
+        self.players.iter().position(|p| p.get_name() == name)
+    }
+
+    #[must_use]
+    pub fn can_check(&self) -> bool {
+        self.players
+            .get(self.current_player)
+            .is_some_and(|p| !p.is_folded() && p.get_current_bet() == self.to_call)
+    }
+
+    #[must_use]
+    pub fn can_call(&self) -> bool {
+        self.players.get(self.current_player).is_some_and(|p| {
+            !p.is_folded() && p.get_chips() > 0 && p.get_current_bet() < self.to_call
+        })
+    }
+
+    #[must_use]
+    pub fn can_bet(&self) -> bool {
+        self.players
+            .get(self.current_player)
+            .is_some_and(|p| !p.is_folded() && p.get_chips() > 0 && self.to_call == 0)
+    }
+
+    /// True when there is a bet to raise, betting is open to the player and they
+    /// can afford a full minimum raise. A player who can only put in less than
+    /// that is limited to all-in.
+    #[must_use]
+    pub fn can_raise(&self) -> bool {
+        self.players.get(self.current_player).is_some_and(|p| {
+            !p.is_folded()
+                && !p.has_acted()
+                && !self.raise_cap_reached()
+                && self.to_call > 0
+                && p.get_current_bet() + p.get_chips() >= self.min_raise_total()
+        })
+    }
+
+    /// Smallest total street bet that counts as a full raise.
+    fn min_raise_total(&self) -> u64 {
+        self.to_call.saturating_add(self.last_raise_size)
+    }
+
+    /// Smallest total the current player may bet or raise to: the amount to call
+    /// plus the last full raise, or everything they have if that is less.
+    #[must_use]
+    pub fn min_raise_to(&self) -> u64 {
+        self.min_raise_total().min(self.max_raise_to())
+    }
+
+    /// Largest total the current player may bet or raise to, i.e. all-in.
+    #[must_use]
+    pub fn max_raise_to(&self) -> u64 {
+        self.players
+            .get(self.current_player)
+            .map_or(0, |p| p.get_current_bet() + p.get_chips())
+    }
+
+    #[must_use]
+    pub fn can_fold(&self) -> bool {
+        self.players
+            .get(self.current_player)
+            .is_some_and(|p| !p.is_folded())
+    }
+
+    /// Players still in the hand in the order they reveal at showdown: the last
+    /// aggressor first, or the first seat after the button if nobody bet, then
+    /// clockwise.
+    #[must_use]
+    pub fn showdown_reveal_order(&self) -> Vec<usize> {
+        let player_count = self.players.len();
+        if player_count == 0 {
+            return Vec::new();
+        }
+        // `end_hand` has already moved the button, so use the one the hand
+        // started with.
+        let button = self
+            .event_log
+            .iter()
+            .find_map(|event| match event {
+                HandEvent::HandStarted { dealer, .. } => Some(*dealer),
+                _ => None,
+            })
+            .unwrap_or(self.dealer_position);
+        let first = self.last_aggressor.unwrap_or((button + 1) % player_count);
+        (0..player_count)
+            .map(|offset| (first + offset) % player_count)
+            .filter(|&i| !self.players[i].is_folded())
+            .collect()
+    }
+
+    /// The made hand `player_idx` holds right now against the board dealt so far,
+    /// or `None` while fewer than five cards are available.
+    #[must_use]
+    pub fn current_hand(&self, player_idx: usize) -> Option<EvaluatedHand> {
+        let hole_cards = self.players.get(player_idx)?.get_hole_cards();
+        if hole_cards.len() + self.community_cards.len() < 5 {
+            return None;
+        }
+        Some(self.variant.evaluate(hole_cards, &self.community_cards))
+    }
+
+    /// The five cards making the first winner's hand after a showdown, for the UI
+    /// to highlight; empty when the hand did not reach showdown.
+    #[must_use]
+    pub fn winning_cards(&self) -> Vec<Card> {
+        self.last_result
+            .as_ref()
+            .filter(|r| r.reason == WinReason::Showdown)
+            .and_then(|r| r.winners.first())
+            .map_or_else(Vec::new, |&winner| {
+                PokerHandEvaluator::best_five(
+                    self.players[winner].get_hole_cards(),
+                    &self.community_cards,
+                )
+            })
+    }
+
+    /// Estimated share of the pot `player_idx` wins from here, by dealing the
+    /// other live players random hands and completing the board `iters` times.
+    /// Only the player's own cards and the board are treated as known; ties count
+    /// as a split.
+    #[must_use]
+    pub fn hero_equity(&self, player_idx: usize, iters: usize) -> f64 {
+        let Some(hero) = self.players.get(player_idx) else {
+            return 0.0;
+        };
+        let hole_card_count = self.variant.hole_card_count();
+        let opponents = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|&(i, p)| i != player_idx && !p.is_folded())
+            .count();
+        let missing = 5usize.saturating_sub(self.community_cards.len());
+        let mut remaining: Vec<Card> = Deck::for_variant(self.variant)
+            .cards
+            .into_iter()
+            .filter(|c| !hero.get_hole_cards().contains(c) && !self.community_cards.contains(c))
+            .collect();
+        let needed = opponents * hole_card_count + missing;
+        if iters == 0 || hero.get_hole_cards().is_empty() || needed > remaining.len() {
+            return 0.0;
+        }
+
+        let mut rng = StdRng::from_entropy();
+        let mut board = self.community_cards.clone();
+        let mut points = 0.0;
+        for _ in 0..iters {
+            for i in 0..needed {
+                let j = rng.gen_range(i..remaining.len());
+                remaining.swap(i, j);
+            }
+            board.truncate(self.community_cards.len());
+            board.extend_from_slice(&remaining[..missing]);
+
+            let hero_hand = self.variant.evaluate(hero.get_hole_cards(), &board);
+            let mut tied = 1;
+            let mut beaten = false;
+            for hole in remaining[missing..needed].chunks(hole_card_count) {
+                match self
+                    .variant
+                    .compare_hands(&self.variant.evaluate(hole, &board), &hero_hand)
+                {
+                    std::cmp::Ordering::Greater => {
+                        beaten = true;
+                        break;
+                    }
+                    std::cmp::Ordering::Equal => tied += 1,
+                    std::cmp::Ordering::Less => {}
+                }
+            }
+            if !beaten {
+                points += 1.0 / f64::from(tied);
+            }
+        }
+        points / iters as f64
+    }
+
+    /// Replace the board outright, for analysis tools rather than normal play.
+    /// The new cards leave the deck and the old board goes back to the bottom
+    /// of it, so the hand can still be played on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for more than five cards, or a card that is repeated or
+    /// already in a player's hand.
+    pub fn set_board(&mut self, cards: Vec<Card>) -> Result<(), &'static str> {
+        if cards.len() > 5 {
+            return Err("Board cannot have more than 5 cards");
+        }
+        let mut seen: HashSet<Card> = self
+            .players
+            .iter()
+            .flat_map(|p| p.get_hole_cards().iter().copied())
+            .collect();
+        if !cards.iter().all(|&card| seen.insert(card)) {
+            return Err("Duplicate card on board");
+        }
+        self.deck.cards.retain(|card| !cards.contains(card));
+        let old = std::mem::replace(&mut self.community_cards, cards);
+        self.deck.cards.extend(
+            old.into_iter()
+                .filter(|card| !self.community_cards.contains(card)),
+        );
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn board_cards(&self) -> &[Card] {
+        &self.community_cards
+    }
+
+    /// The three flop cards, or an empty slice before the flop.
+    #[must_use]
+    pub fn flop(&self) -> &[Card] {
+        self.community_cards.get(..3).unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn turn(&self) -> Option<Card> {
+        self.community_cards.get(3).copied()
+    }
+
+    #[must_use]
+    pub fn river(&self) -> Option<Card> {
+        self.community_cards.get(4).copied()
+    }
+
+    /// Every action the current player may take, with bet/raise sizing bounds.
+    #[must_use]
+    pub fn legal_actions(&self) -> Vec<LegalAction> {
+        let Some(player) = self.players.get(self.current_player) else {
+            return Vec::new();
+        };
+        if !self.pending_action || player.is_folded() || player.is_all_in() {
+            return Vec::new();
+        }
+
+        let sizing = Some(BetSizing {
+            min: self.min_bet,
+            max: self.max_bet,
+        });
+        let candidates = [
+            (PlayerAction::Fold, self.can_fold(), None),
+            (PlayerAction::Check, self.can_check(), None),
+            (PlayerAction::Call, self.can_call(), None),
+            (PlayerAction::Bet, self.can_bet(), sizing),
+            (PlayerAction::Raise, self.can_raise(), sizing),
+            (
+                PlayerAction::AllIn,
+                player.get_chips() > 0 && self.is_action_legal(PlayerAction::AllIn),
+                None,
+            ),
+        ];
+
+        candidates
+            .into_iter()
+            .filter(|&(_, legal, _)| legal)
+            .map(|(action, _, sizing)| LegalAction { action, sizing })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn get_call_amount(&self) -> u64 {
+        self.players
+            .get(self.current_player)
+            .map_or(0, |p| self.owed_by(p))
+    }
+
+    /// What each player still in the hand must add to match `to_call`.
+    #[must_use]
+    pub fn outstanding_bets(&self) -> Vec<(usize, u64)> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.is_folded())
+            .map(|(i, p)| (i, self.owed_by(p)))
+            .collect()
+    }
+
+    fn owed_by(&self, player: &Player) -> u64 {
+        self.to_call.saturating_sub(player.get_current_bet())
+    }
+
+    #[must_use]
+    pub fn effective_stack(&self, a: usize, b: usize) -> u64 {
+        match (self.players.get(a), self.players.get(b)) {
+            (Some(pa), Some(pb)) => pa.get_chips().min(pb.get_chips()),
+            _ => 0,
+        }
+    }
+
+    /// Split the chips invested so far into a main pot and side pots, one layer
+    /// per distinct all-in amount among players still in the hand. A bet nobody
+    /// has matched is left out, since it goes back to the bettor rather than
+    /// into any pot, and a layer no remaining player can win joins the one below.
+    /// Showdowns pay out through these layers.
+    #[must_use]
+    pub fn side_pots(&self) -> Vec<SidePot> {
+        let uncalled = self.uncalled_bet();
+        let invested: Vec<u64> = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let excess = uncalled.filter(|&(top, _)| top == i).map_or(0, |(_, a)| a);
+                p.get_total_invested() - excess
+            })
+            .collect();
+        let mut caps: Vec<u64> = self
+            .players
+            .iter()
+            .zip(&invested)
+            .filter(|(p, _)| !p.is_folded() && p.is_all_in())
+            .map(|(_, &amount)| amount)
+            .collect();
+        caps.push(u64::MAX);
+        caps.sort_unstable();
+        caps.dedup();
+
+        let mut pots: Vec<SidePot> = Vec::new();
+        let mut previous_cap = 0;
+        for cap in caps {
+            let amount = invested
+                .iter()
+                .map(|&put_in| put_in.min(cap) - put_in.min(previous_cap))
+                .sum();
+            let eligible: Vec<usize> = self
+                .players
+                .iter()
+                .zip(&invested)
+                .enumerate()
+                .filter(|(_, (p, &put_in))| !p.is_folded() && (!p.is_all_in() || put_in >= cap))
+                .map(|(i, _)| i)
+                .collect();
+            previous_cap = cap;
+            if amount == 0 {
+                continue;
+            }
+            match pots.last_mut() {
+                Some(below) if eligible.is_empty() => below.amount += amount,
+                _ => pots.push(SidePot { amount, eligible }),
+            }
+        }
+        pots
+    }
+
+    /// The most `player_idx` could collect from the chips already in: every pot
+    /// layer they are eligible for.
+    #[must_use]
+    pub fn max_winnable(&self, player_idx: usize) -> u64 {
+        self.side_pots()
+            .iter()
+            .filter(|pot| pot.eligible.contains(&player_idx))
+            .map(|pot| pot.amount)
+            .sum()
+    }
+
+    /// Effective stack of `player` against the deepest opponent still in the hand.
+    #[must_use]
+    pub fn effective_stack_for(&self, player: usize) -> u64 {
+        let Some(hero) = self.players.get(player) else {
+            return 0;
+        };
+        let deepest_opponent = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|&(i, p)| i != player && !p.is_folded())
+            .map(|(_, p)| p.get_chips())
+            .max()
+            .unwrap_or(0);
+        hero.get_chips().min(deepest_opponent)
+    }
+
+    #[must_use]
+    pub fn pot_odds_detail(&self) -> PotOdds {
+        let call = self.get_call_amount();
+        let pot = self.pot;
+        if call == 0 {
+            return PotOdds {
+                call,
+                pot,
+                ratio: 0.0,
+                pct: 0.0,
+                breakeven_equity: 0.0,
+            };
+        }
+
+        let breakeven_equity = call as f64 / pot.saturating_add(call) as f64;
+        PotOdds {
+            call,
+            pot,
+            ratio: pot as f64 / call as f64,
+            pct: breakeven_equity * 100.0,
+            breakeven_equity,
+        }
+    }
+
+    /// Breakeven equity for calling when `expected_future_bets` more chips are
+    /// expected to be won on later streets if the draw hits:
+    /// `call / (pot + call + expected_future_bets)`. Zero when there is nothing to call.
+    #[must_use]
+    pub fn implied_odds(&self, expected_future_bets: u64) -> f64 {
+        let call = self.get_call_amount();
+        if call == 0 {
+            return 0.0;
+        }
+        let winnings = self
+            .pot
+            .saturating_add(call)
+            .saturating_add(expected_future_bets);
+        call as f64 / winnings as f64
+    }
+
+    pub fn update_pot_odds(&mut self) {
+        let call_amount = self.get_call_amount();
+        let total_pot = self.pot.saturating_add(call_amount);
+        self.pot_odds = if call_amount > 0 && total_pot > 0 {
+            call_amount as f32 / total_pot as f32
+        } else {
+            0.0
+        };
+    }
+}
+
+impl Default for PokerGameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fluent construction of a `PokerGameState` with custom seats, dealer, deck and
+/// blinds, mainly for scripted games and tests.
+#[derive(Clone)]
+pub struct GameBuilder {
+    players: Vec<(String, u64)>,
+    dealer: usize,
+    deck: Option<Deck>,
+    small_blind: u64,
+    big_blind: u64,
+}
+
+impl Default for GameBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        GameBuilder {
+            players: vec![
+                ("Alice".to_string(), INITIAL_CHIPS),
+                ("Bob".to_string(), INITIAL_CHIPS),
+            ],
+            dealer: 0,
+            deck: None,
+            small_blind: SMALL_BLIND_CHIPS,
+            big_blind: BIG_BLIND_CHIPS,
+        }
+    }
+
+    #[must_use]
+    pub fn players(mut self, players: &[(&str, u64)]) -> Self {
+        self.players = players
+            .iter()
+            .map(|&(name, chips)| (name.to_string(), chips))
+            .collect();
+        self
+    }
+
+    #[must_use]
+    pub fn dealer(mut self, dealer: usize) -> Self {
+        self.dealer = dealer;
+        self
+    }
+
+    /// Deal every hand from `deck` in its current order instead of shuffling.
+    #[must_use]
+    pub fn deck(mut self, deck: Deck) -> Self {
+        self.deck = Some(deck);
+        self
+    }
+
+    #[must_use]
+    pub fn blinds(mut self, small_blind: u64, big_blind: u64) -> Self {
+        self.small_blind = small_blind;
+        self.big_blind = big_blind;
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> PokerGameState {
+        let mut state = PokerGameState::new();
+        state.players = self
+            .players
+            .into_iter()
+            .map(|(name, chips)| Player::new(name, chips))
+            .collect();
+        state.dealer_position = self.dealer % state.players.len().max(1);
+        state.scripted_deck = self.deck;
+        state.small_blind = self.small_blind;
+        state.big_blind = self.big_blind;
+        state
+    }
+}
+
+/// Summary of a completed hand kept by `GameRunner`: each seat's stack after
+/// the pot was awarded and the final pot size.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandRecord {
+    pub hand_number: u64,
+    pub stacks: Vec<u64>,
+    pub pot: u64,
+}
+
+/// Drives a `PokerGameState` across many hands and records how each one ended,
+/// for long bot sessions.
+#[derive(Clone)]
+pub struct GameRunner {
+    pub state: PokerGameState,
+    hands: Vec<HandRecord>,
+    decision_rng: StdRng,
+}
+
+impl GameRunner {
+    #[must_use]
+    pub fn new(state: PokerGameState) -> Self {
+        let decision_rng = Self::decision_rng_for(&state);
+        GameRunner {
+            state,
+            hands: Vec::new(),
+            decision_rng,
+        }
+    }
+
+    /// Start the next hand and reseed the decision RNG for it.
+    pub fn start_new_hand(&mut self) -> Result<(), &'static str> {
+        self.state.start_new_hand()?;
+        self.decision_rng = Self::decision_rng_for(&self.state);
+        Ok(())
+    }
+
+    /// Randomness for bot choices such as bluff frequency. It is derived from the
+    /// game seed and hand number but separate from the shuffle, so a seeded
+    /// session replays the same decisions.
+    pub fn decision_rng(&mut self) -> &mut StdRng {
+        &mut self.decision_rng
+    }
+
+    fn decision_rng_for(state: &PokerGameState) -> StdRng {
+        let hand_salt = state.hand_number.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        StdRng::seed_from_u64(state.seed ^ hand_salt ^ 0xD1B5_4A32_D192_ED03)
+    }
+
+    /// Apply `action` and record the hand if it just completed.
+    pub fn perform_action(&mut self, action: PlayerAction) -> Result<String, &'static str> {
+        let message = self.state.perform_action(action)?;
+        if self.state.stage == GameStage::HandComplete {
+            self.hands.push(HandRecord {
+                hand_number: self.state.hand_number,
+                stacks: self.state.players.iter().map(Player::get_chips).collect(),
+                pot: self.state.pot,
+            });
+        }
+        Ok(message)
+    }
+
+    #[must_use]
+    pub fn get_hands(&self) -> &[HandRecord] {
+        &self.hands
+    }
+
+    /// One header row naming each seat, then one row per completed hand with the
+    /// ending stacks and pot.
+    #[must_use]
+    pub fn session_csv(&self) -> String {
+        let mut header = vec!["hand".to_string()];
+        header.extend(self.state.players.iter().map(|p| p.get_name().to_string()));
+        header.push("pot".to_string());
+
+        let mut rows = vec![header.join(",")];
+        for record in &self.hands {
+            let mut row = vec![record.hand_number.to_string()];
+            row.extend(record.stacks.iter().map(ToString::to_string));
+            row.push(record.pot.to_string());
+            rows.push(row.join(","));
+        }
+        rows.join("\n")
+    }
+}
+
+/// A multi-hand session saved between hands: seats, stacks, button, blinds and
+/// the seed and hand count that drive every later shuffle. Restoring it with
+/// `into_state` deals the same hands the original session would have. The
+/// table rules are saved too; the scripted deck, pending straddle, sitting-out
+/// seats, hooks and observers are not. Fields missing from an older save take
+/// a fresh game's defaults.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default = "Session::fresh")]
+pub struct Session {
+    pub variant: Variant,
+    pub seed: u64,
+    pub hand_number: u64,
+    pub dealer_position: usize,
+    pub small_blind: u64,
+    pub big_blind: u64,
+    /// `(name, chips)` for each seat in order.
+    pub players: Vec<(String, u64)>,
+    pub chip_denomination: u64,
+    pub burn_cards: bool,
+    pub burn_per_street: usize,
+    pub max_raises_per_street: Option<u32>,
+    pub run_twice_on_all_in: bool,
+    pub hi_lo: bool,
+    pub owe_missed_blinds: bool,
+}
+
+impl Session {
+    /// Save `state` between hands.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err("Cannot save a session mid-hand")` while a hand is in
+    /// progress, since the chips already in the pot would be lost.
+    pub fn capture(state: &PokerGameState) -> Result<Self, &'static str> {
+        if !matches!(
+            state.stage,
+            GameStage::WaitingToStart | GameStage::HandComplete
+        ) {
+            return Err("Cannot save a session mid-hand");
+        }
+        Ok(Self::from_state(state))
+    }
+
+    fn from_state(state: &PokerGameState) -> Self {
+        Session {
+            variant: state.variant,
+            seed: state.seed,
+            hand_number: state.hand_number,
+            dealer_position: state.dealer_position,
+            small_blind: state.small_blind,
+            big_blind: state.big_blind,
+            players: state
+                .players
+                .iter()
+                .map(|p| (p.get_name().to_string(), p.get_chips()))
+                .collect(),
+            chip_denomination: state.chip_denomination,
+            burn_cards: state.burn_cards,
+            burn_per_street: state.burn_per_street,
+            max_raises_per_street: state.max_raises_per_street,
+            run_twice_on_all_in: state.run_twice_on_all_in,
+            hi_lo: state.hi_lo,
+            owe_missed_blinds: state.owe_missed_blinds,
+        }
+    }
+
+    fn fresh() -> Self {
+        Self::from_state(&PokerGameState::new())
+    }
+
+    /// A fresh state, waiting for the next hand, that continues this session.
+    #[must_use]
+    pub fn into_state(self) -> PokerGameState {
+        let mut state = PokerGameState::seeded_with_variant(self.seed, self.variant);
+        state.hand_number = self.hand_number;
+        state.dealer_position = self.dealer_position;
+        state.small_blind = self.small_blind;
+        state.big_blind = self.big_blind;
+        state.last_raise_size = self.big_blind;
+        state.players = self
+            .players
+            .into_iter()
+            .map(|(name, chips)| Player::new(name, chips))
+            .collect();
+        state.chip_denomination = self.chip_denomination;
+        state.burn_cards = self.burn_cards;
+        state.burn_per_street = self.burn_per_street;
+        state.max_raises_per_street = self.max_raises_per_street;
+        state.run_twice_on_all_in = self.run_twice_on_all_in;
+        state.hi_lo = self.hi_lo;
+        state.owe_missed_blinds = self.owe_missed_blinds;
+        state
+    }
+
+    /// Write the session to `path` as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Read a session written by `save`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a saved session.
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+}
+
+/// Why a hand-history line could not be parsed; `line` is 1-based.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// Recover the action sequence from text produced by
+/// `PokerGameState::to_hand_history_text`. Each action is paired with the chips
+/// it put in, so a bet or raise can be replayed by setting `bet_amount` first.
+pub fn parse_hand_history(text: &str) -> Result<Vec<(PlayerAction, u64)>, ParseError> {
+    let mut actions = Vec::new();
+    let mut street_bets: HashMap<&str, u64> = HashMap::new();
+    let mut in_actions = false;
+
+    for (idx, line) in text.lines().enumerate() {
+        let err = |reason| ParseError {
+            line: idx + 1,
+            reason,
+        };
+        let line = line.trim();
+        if line.starts_with("***") {
+            match line.split_whitespace().nth(1) {
+                Some("HOLE") => in_actions = true,
+                Some("FLOP" | "TURN" | "RIVER") => street_bets.clear(),
+                _ => break,
+            }
+            continue;
+        }
+        let Some((name, rest)) = line.rsplit_once(':') else {
+            continue;
+        };
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let number = |word: Option<&&str>| {
+            word.and_then(|w| w.parse::<u64>().ok())
+                .ok_or_else(|| err("Expected a chip amount"))
+        };
+        let name = name.trim();
+        let bet = street_bets.entry(name).or_insert(0);
+
+        if words.first() == Some(&"posts") {
+            *bet += number(words.last())?;
+            continue;
+        }
+        if !in_actions {
+            continue;
+        }
+
+        let all_in = line.ends_with("and is all-in");
+        let (action, amount) = match words.first().copied() {
+            Some("folds") => (PlayerAction::Fold, 0),
+            Some("checks") => (PlayerAction::Check, 0),
+            Some("calls") => (PlayerAction::Call, number(words.get(1))?),
+            Some("bets") => (PlayerAction::Bet, number(words.get(1))?),
+            Some("raises") => {
+                let total = number(words.get(3))?;
+                if words.get(2) != Some(&"to") || total <= *bet {
+                    return Err(err("Malformed raise"));
+                }
+                (PlayerAction::Raise, total - *bet)
+            }
+            _ => return Err(err("Unknown action")),
+        };
+        *bet += amount;
+        actions.push((if all_in { PlayerAction::AllIn } else { action }, amount));
+    }
+
+    if in_actions {
+        Ok(actions)
+    } else {
+        Err(ParseError {
+            line: text.lines().count(),
+            reason: "Missing hole cards section",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deck_creation() {
+        let deck = Deck::new();
+        assert_eq!(deck.len(), 52);
+    }
+
+    #[test]
+    fn test_deck_shuffle() {
+        let mut deck1 = Deck::new();
+        let mut deck2 = Deck::new();
+        deck1.shuffle();
+        deck2.shuffle();
+
+        let cards1: Vec<String> = deck1.cards.iter().map(|c| c.to_string()).collect();
+        let cards2: Vec<String> = deck2.cards.iter().map(|c| c.to_string()).collect();
+
+        assert_ne!(cards1, cards2);
+    }
+
+    #[test]
+    fn test_deck_deal() {
+        let mut deck = Deck::new();
+        let cards = deck.deal(5).unwrap();
+        assert_eq!(cards.len(), 5);
+        assert_eq!(deck.len(), 47);
+    }
+
+    #[test]
+    fn test_deck_deal_insufficient() {
+        let mut deck = Deck::new();
+        let _ = deck.deal(50).unwrap();
+        assert!(deck.deal(5).is_none());
+    }
+
+    #[test]
+    fn test_card_to_string() {
+        let ace_spades = Card::new(14, Suit::Spades);
+        assert_eq!(ace_spades.to_string(), "A♠");
+
+        let ten_hearts = Card::new(10, Suit::Hearts);
+        assert_eq!(ten_hearts.to_string(), "10♥");
+
+        let two_clubs = Card::new(2, Suit::Clubs);
+        assert_eq!(two_clubs.to_string(), "2♣");
+    }
+
+    #[test]
+    fn test_card_from_string() {
+        assert_eq!(Card::from_string("A♠"), Some(Card::new(14, Suit::Spades)));
+        assert_eq!(Card::from_string("10♥"), Some(Card::new(10, Suit::Hearts)));
+        assert_eq!(Card::from_string("2♣"), Some(Card::new(2, Suit::Clubs)));
+        assert_eq!(Card::from_string("invalid"), None);
+    }
+
+    #[test]
+    fn test_card_is_red() {
+        assert!(Card::new(5, Suit::Hearts).is_red());
+        assert!(Card::new(5, Suit::Diamonds).is_red());
+        assert!(!Card::new(5, Suit::Spades).is_red());
+        assert!(!Card::new(5, Suit::Clubs).is_red());
+    }
+
+    #[test]
+    fn test_player_betting() {
+        let mut player = Player::new("Test".to_string(), 1000);
+        assert_eq!(player.get_chips(), 1000);
+
+        let result = player.bet(500);
+        assert!(result.is_ok());
+        assert_eq!(player.get_chips(), 500);
+        assert_eq!(player.get_current_bet(), 500);
+        assert!(!player.is_all_in());
+
+        let result = player.bet(500);
+        assert!(result.is_ok());
+        assert_eq!(player.get_chips(), 0);
+        assert!(player.is_all_in());
+
+        let result = player.bet(100);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hand_evaluator_high_card() {
+        let hole_cards = vec![Card::new(2, Suit::Spades), Card::new(5, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(8, Suit::Diamonds),
+            Card::new(10, Suit::Clubs),
+            Card::new(12, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::HighCard);
+    }
+
+    #[test]
+    fn test_hand_evaluator_pair() {
+        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(10, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Diamonds),
+            Card::new(8, Suit::Clubs),
+            Card::new(12, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Pair);
+    }
+
+    #[test]
+    fn test_hand_evaluator_flush() {
+        let hole_cards = vec![Card::new(2, Suit::Hearts), Card::new(7, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Hearts),
+            Card::new(10, Suit::Hearts),
+            Card::new(12, Suit::Hearts),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Flush);
+    }
+
+    #[test]
+    fn test_hand_evaluator_straight() {
+        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(8, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(6, Suit::Diamonds),
+            Card::new(7, Suit::Clubs),
+            Card::new(9, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Straight);
+    }
+
+    #[test]
+    fn test_hand_evaluator_full_house() {
+        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(5, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Diamonds),
+            Card::new(10, Suit::Clubs),
+            Card::new(10, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::FullHouse);
+    }
+
+    #[test]
+    fn test_hand_evaluator_four_of_a_kind() {
+        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(5, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Diamonds),
+            Card::new(5, Suit::Clubs),
+            Card::new(10, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::FourOfAKind);
+    }
+
+    #[test]
+    fn test_hand_evaluator_straight_flush() {
+        let hole_cards = vec![Card::new(6, Suit::Hearts), Card::new(7, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(8, Suit::Hearts),
+            Card::new(9, Suit::Hearts),
+            Card::new(10, Suit::Hearts),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::StraightFlush);
+    }
+
+    #[test]
+    fn test_hand_evaluator_royal_flush() {
+        let hole_cards = vec![Card::new(10, Suit::Hearts), Card::new(14, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(11, Suit::Hearts),
+            Card::new(12, Suit::Hearts),
+            Card::new(13, Suit::Hearts),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::RoyalFlush);
+    }
+
+    #[test]
+    fn test_hand_evaluator_two_pair() {
+        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(10, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Diamonds),
+            Card::new(10, Suit::Clubs),
+            Card::new(12, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::TwoPair);
+    }
+
+    #[test]
+    fn test_hand_evaluator_three_of_a_kind() {
+        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(8, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Diamonds),
+            Card::new(5, Suit::Clubs),
+            Card::new(12, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::ThreeOfAKind);
+    }
+
+    #[test]
+    fn test_hand_evaluator_ace_low_straight() {
+        let hole_cards = vec![Card::new(2, Suit::Spades), Card::new(3, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(4, Suit::Diamonds),
+            Card::new(5, Suit::Clubs),
+            Card::new(14, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Straight);
+        assert_eq!(evaluated.primary_values, vec![5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_hand_evaluator_straight_with_higher_non_straight_cards() {
+        let hole_cards = vec![Card::new(14, Suit::Spades), Card::new(13, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(11, Suit::Diamonds),
+            Card::new(10, Suit::Clubs),
+            Card::new(9, Suit::Spades),
+            Card::new(8, Suit::Hearts),
+            Card::new(7, Suit::Diamonds),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Straight);
+        assert_eq!(evaluated.primary_values, vec![11, 10, 9, 8, 7]);
+    }
+
+    #[test]
+    fn test_omaha_nut_flush_uses_two_suited_hole_cards() {
+        let hole_cards = [
+            Card::new(14, Suit::Hearts),
+            Card::new(13, Suit::Hearts),
+            Card::new(2, Suit::Clubs),
+            Card::new(3, Suit::Diamonds),
+        ];
+        let community_cards = vec![
+            Card::new(12, Suit::Hearts),
+            Card::new(7, Suit::Hearts),
+            Card::new(4, Suit::Hearts),
+            Card::new(9, Suit::Spades),
+            Card::new(11, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate_omaha(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Flush);
+        assert_eq!(evaluated.primary_values, vec![14, 13, 12, 7, 4]);
+    }
+
+    #[test]
+    fn test_omaha_single_suited_hole_card_makes_no_flush() {
+        let hole_cards = [
+            Card::new(14, Suit::Hearts),
+            Card::new(13, Suit::Clubs),
+            Card::new(2, Suit::Clubs),
+            Card::new(3, Suit::Diamonds),
+        ];
+        let community_cards = vec![
+            Card::new(12, Suit::Hearts),
+            Card::new(7, Suit::Hearts),
+            Card::new(4, Suit::Hearts),
+            Card::new(9, Suit::Hearts),
+            Card::new(11, Suit::Spades),
+        ];
+
+        let holdem = PokerHandEvaluator::evaluate(&hole_cards[..2], &community_cards);
+        assert_eq!(holdem.rank, HandRank::Flush);
+
+        let omaha = PokerHandEvaluator::evaluate_omaha(&hole_cards, &community_cards);
+        assert_ne!(omaha.rank, HandRank::Flush);
+    }
+
+    #[test]
+    fn test_short_deck_creation() {
+        let deck = Deck::for_variant(Variant::ShortDeck);
+        assert_eq!(deck.len(), 36);
+        assert!(deck.cards.iter().all(|c| c.rank >= 6));
+    }
+
+    #[test]
+    fn test_variant_hole_card_count() {
+        let mut state = PokerGameState::with_variant(Variant::Omaha);
+        state.start_new_hand().unwrap();
+        assert!(state.players.iter().all(|p| p.get_hole_cards().len() == 4));
+        assert_eq!(Variant::TexasHoldem.hole_card_count(), 2);
+    }
+
+    #[test]
+    fn test_short_deck_flush_beats_full_house() {
+        let flush = EvaluatedHand::new(HandRank::Flush, vec![13, 11, 9, 8, 6], Vec::new());
+        let full_house = EvaluatedHand::new(HandRank::FullHouse, vec![14, 13], Vec::new());
+
+        assert_eq!(
+            Variant::TexasHoldem.compare_hands(&flush, &full_house),
+            std::cmp::Ordering::Less
+        );
+        assert_eq!(
+            Variant::ShortDeck.compare_hands(&flush, &full_house),
+            std::cmp::Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_flush_vs_full_house_under_both_rule_sets() {
+        let community_cards = vec![
+            Card::new(9, Suit::Clubs),
+            Card::new(13, Suit::Hearts),
+            Card::new(13, Suit::Spades),
+            Card::new(7, Suit::Hearts),
+            Card::new(6, Suit::Hearts),
+        ];
+        let flush_hole = vec![Card::new(14, Suit::Hearts), Card::new(10, Suit::Hearts)];
+        let full_house_hole = vec![Card::new(9, Suit::Hearts), Card::new(9, Suit::Spades)];
+
+        for variant in [Variant::TexasHoldem, Variant::ShortDeck] {
+            let flush = variant.evaluate(&flush_hole, &community_cards);
+            let full_house = variant.evaluate(&full_house_hole, &community_cards);
+            assert_eq!(flush.rank, HandRank::Flush);
+            assert_eq!(full_house.rank, HandRank::FullHouse);
+
+            let expected = if variant == Variant::ShortDeck {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Less
+            };
+            assert_eq!(variant.compare_hands(&flush, &full_house), expected);
+        }
+    }
+
+    #[test]
+    fn test_short_deck_ace_low_straight() {
+        let hole_cards = vec![Card::new(14, Suit::Spades), Card::new(6, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(7, Suit::Diamonds),
+            Card::new(8, Suit::Clubs),
+            Card::new(9, Suit::Spades),
+        ];
+
+        let short_deck = PokerHandEvaluator::evaluate_for_variant(
+            &hole_cards,
+            &community_cards,
+            Variant::ShortDeck,
+        );
+        assert_eq!(short_deck.rank, HandRank::Straight);
+        assert_eq!(short_deck.primary_values, vec![9, 8, 7, 6, 5]);
+
+        let standard = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(standard.rank, HandRank::HighCard);
+    }
+
+    #[test]
+    fn test_rename_player_and_find_by_name() {
+        let mut state = PokerGameState::new();
+        state.players[1].set_name("Carol".to_string());
+
+        assert_eq!(state.players[1].get_name(), "Carol");
+        assert_eq!(state.player_index_by_name("Carol"), Some(1));
+        assert_eq!(state.player_index_by_name("Alice"), Some(0));
+        assert_eq!(state.player_index_by_name("Bob"), None);
+    }
+
+    #[test]
+    fn test_reveal_all_only_at_showdown() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.stage, GameStage::Preflop);
+        assert!(state.reveal_all().is_empty());
+
+        state.stage = GameStage::Showdown;
+        let revealed = state.reveal_all();
+        assert_eq!(revealed.len(), 2);
+        assert_eq!(revealed[0].0, 0);
+        assert_eq!(revealed[0].1, state.players[0].get_hole_cards());
+        assert_eq!(revealed[1].1, state.players[1].get_hole_cards());
+    }
+
+    #[test]
+    fn test_set_bet_amount_clamps_to_bounds() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let (min_bet, max_bet) = (state.min_bet, state.max_bet);
+
+        assert_eq!(state.set_bet_amount(1), min_bet);
+        assert_eq!(state.bet_amount, min_bet);
+
+        assert_eq!(state.set_bet_amount(u64::MAX), max_bet);
+        assert_eq!(state.bet_amount, max_bet);
+
+        assert_eq!(state.set_bet_amount(min_bet + 1), min_bet + 1);
+    }
+
+    #[test]
+    fn test_pot_odds_detail() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.pot = 300;
+        state.to_call = state.players[state.current_player].get_current_bet() + 100;
+
+        let odds = state.pot_odds_detail();
+        assert_eq!(odds.call, 100);
+        assert_eq!(odds.pot, 300);
+        assert!((odds.ratio - 3.0).abs() < f64::EPSILON);
+        assert!((odds.breakeven_equity - 0.25).abs() < f64::EPSILON);
+        assert!((odds.pct - 25.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_dealing_plan_preflop() {
+        let state = PokerGameState::new();
+        let plan = state.dealing_plan();
+
+        assert_eq!(
+            plan[..5],
+            [
+                DealStep::Burn,
+                DealStep::HoleCard { player: 0 },
+                DealStep::HoleCard { player: 0 },
+                DealStep::HoleCard { player: 1 },
+                DealStep::HoleCard { player: 1 },
+            ]
+        );
+        assert_eq!(
+            plan[5..7],
+            [DealStep::Burn, DealStep::Community { count: 3 }]
+        );
+        assert_eq!(plan.len(), 11);
+    }
+
+    #[test]
+    fn test_effective_stack() {
+        let mut state = PokerGameState::new();
+        state.players[0].chips = 800;
+        state.players[1].chips = 1500;
+
+        assert_eq!(state.effective_stack(0, 1), 800);
+        assert_eq!(state.effective_stack(1, 0), 800);
+        assert_eq!(state.effective_stack(0, 5), 0);
+    }
+
+    #[test]
+    fn test_effective_stack_for_multiway() {
+        let mut state = PokerGameState::new();
+        state.players.push(Player::new("Carol".to_string(), 3000));
+        state.players[0].chips = 2000;
+        state.players[1].chips = 500;
+
+        assert_eq!(state.effective_stack_for(0), 2000);
+        assert_eq!(state.effective_stack_for(1), 500);
+
+        state.players[2].set_folded(true);
+        assert_eq!(state.effective_stack_for(0), 500);
+    }
+
+    #[test]
+    fn test_hand_result_fold_win() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let folder = state.current_player;
+        let winner = (folder + 1) % state.players.len();
+        state.perform_action(PlayerAction::Fold).unwrap();
+
+        assert_eq!(state.stage, GameStage::HandComplete);
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.winners, vec![winner]);
+        assert_eq!(result.amounts, vec![2 * SMALL_BLIND_CHIPS]);
+        assert_eq!(result.best, None);
+        assert_eq!(result.reason, WinReason::Fold);
+    }
+
+    #[test]
+    fn test_hand_result_showdown_win() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.players[0].hole_cards =
+            vec![Card::new(14, Suit::Spades), Card::new(14, Suit::Hearts)];
+        state.players[1].hole_cards = vec![Card::new(7, Suit::Clubs), Card::new(2, Suit::Diamonds)];
+        state.community_cards = vec![
+            Card::new(3, Suit::Spades),
+            Card::new(9, Suit::Hearts),
+            Card::new(11, Suit::Diamonds),
+            Card::new(13, Suit::Clubs),
+            Card::new(5, Suit::Spades),
+        ];
+        state.stage = GameStage::Showdown;
+        let pot = state.pot;
+        state.determine_winner();
+
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.winners, vec![0]);
+        assert_eq!(
+            result.amounts,
+            vec![pot - (BIG_BLIND_CHIPS - SMALL_BLIND_CHIPS)]
+        );
+        assert_eq!(result.best.map(|h| h.rank), Some(HandRank::Pair));
+        assert_eq!(result.reason, WinReason::Showdown);
+    }
+
+    #[test]
+    fn test_seeded_sessions_play_identical_hands() {
+        let mut first = PokerGameState::new_seeded(42);
+        let mut second = PokerGameState::new_seeded(42);
+
+        for _ in 0..3 {
+            first.start_new_hand().unwrap();
+            second.start_new_hand().unwrap();
+            assert_eq!(first.deck.cards, second.deck.cards);
+            for (a, b) in first.players.iter().zip(&second.players) {
+                assert_eq!(a.get_hole_cards(), b.get_hole_cards());
+            }
+
+            first.perform_action(PlayerAction::Fold).unwrap();
+            second.perform_action(PlayerAction::Fold).unwrap();
+            for (a, b) in first.players.iter().zip(&second.players) {
+                assert_eq!(a.get_chips(), b.get_chips());
+            }
+        }
+
+        let mut other = PokerGameState::new_seeded(43);
+        other.start_new_hand().unwrap();
+        let mut replay = PokerGameState::new_seeded(42);
+        replay.start_new_hand().unwrap();
+        assert_ne!(other.deck.cards, replay.deck.cards);
+    }
+
+    #[test]
+    fn test_legal_actions_big_blind_option() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let bb_position = (state.dealer_position + 2) % state.players.len();
+        state.perform_action(PlayerAction::Call).unwrap();
+
+        assert_eq!(state.stage, GameStage::Preflop);
+        assert_eq!(state.current_player, bb_position);
+
+        let actions = state.legal_actions();
+        let kinds: Vec<PlayerAction> = actions.iter().map(|a| a.action).collect();
+        assert!(kinds.contains(&PlayerAction::Check));
+        assert!(!kinds.contains(&PlayerAction::Call));
+        assert!(!kinds.contains(&PlayerAction::Bet));
+
+        let raise = actions
+            .iter()
+            .find(|a| a.action == PlayerAction::Raise)
+            .unwrap();
+        assert_eq!(
+            raise.sizing,
+            Some(BetSizing {
+                min: BIG_BLIND_CHIPS,
+                max: INITIAL_CHIPS - BIG_BLIND_CHIPS,
+            })
+        );
+        state.set_bet_amount(BIG_BLIND_CHIPS);
+        state.perform_action(PlayerAction::Raise).unwrap();
+        assert_eq!(state.to_call, BIG_BLIND_CHIPS * 2);
+    }
+
+    #[test]
+    fn test_can_raise_facing_bet_with_deep_stack() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.to_call = 200;
+
+        assert!(state.can_raise());
+        assert!(state
+            .legal_actions()
+            .iter()
+            .any(|a| a.action == PlayerAction::Raise));
+    }
+
+    #[test]
+    fn test_short_stack_facing_bet_can_only_go_all_in() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.to_call = 200;
+        state.last_raise_size = 200 - BIG_BLIND_CHIPS;
+        let current = state.current_player;
+        state.players[current].chips = 300 - state.players[current].get_current_bet();
+
+        assert!(!state.can_raise());
+        let kinds: Vec<PlayerAction> = state.legal_actions().iter().map(|a| a.action).collect();
+        assert!(kinds.contains(&PlayerAction::Call));
+        assert!(kinds.contains(&PlayerAction::AllIn));
+        assert!(!kinds.contains(&PlayerAction::Raise));
+    }
+
+    #[test]
+    fn test_rebuy_only_between_hands() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let chips = state.players[0].get_chips();
+        assert!(state.rebuy(0, 500).is_err());
+        assert_eq!(state.players[0].get_chips(), chips);
+
+        state.perform_action(PlayerAction::Fold).unwrap();
+        assert_eq!(state.stage, GameStage::HandComplete);
+        let chips = state.players[0].get_chips();
+        assert!(state.rebuy(0, 500).is_ok());
+        assert_eq!(state.players[0].get_chips(), chips + 500);
+        assert!(state.rebuy(7, 500).is_err());
+    }
+
+    #[test]
+    fn test_auto_muck_loser_is_not_revealed() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.players[0].hole_cards =
+            vec![Card::new(14, Suit::Spades), Card::new(14, Suit::Hearts)];
+        state.players[1].hole_cards = vec![Card::new(7, Suit::Clubs), Card::new(2, Suit::Diamonds)];
+        state.community_cards = vec![
+            Card::new(3, Suit::Spades),
+            Card::new(9, Suit::Hearts),
+            Card::new(11, Suit::Diamonds),
+            Card::new(13, Suit::Clubs),
+            Card::new(5, Suit::Spades),
+        ];
+        state.players[0].set_auto_muck(true);
+        state.players[1].set_auto_muck(true);
+        state.stage = GameStage::Showdown;
+        state.determine_winner();
+
+        assert_eq!(state.last_result.as_ref().unwrap().winners, vec![0]);
+        let revealed = state.revealed_hands();
+        assert_eq!(revealed.len(), 1);
+        assert_eq!(revealed[0].0, 0);
+
+        state.players[1].set_auto_muck(false);
+        assert_eq!(state.revealed_hands().len(), 2);
+    }
+
+    #[test]
+    fn test_full_house_uses_highest_trips() {
+        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(5, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Diamonds),
+            Card::new(13, Suit::Clubs),
+            Card::new(13, Suit::Spades),
+            Card::new(13, Suit::Hearts),
+            Card::new(2, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::FullHouse);
+        assert_eq!(evaluated.primary_values, vec![13, 5]);
+    }
+
+    #[test]
+    fn test_full_house_shared_trips_best_pair_wins() {
+        let community_cards = vec![
+            Card::new(13, Suit::Clubs),
+            Card::new(13, Suit::Spades),
+            Card::new(13, Suit::Hearts),
+            Card::new(9, Suit::Diamonds),
+            Card::new(2, Suit::Spades),
+        ];
+        let queens = vec![Card::new(12, Suit::Spades), Card::new(12, Suit::Hearts)];
+        let nine_two = vec![Card::new(9, Suit::Spades), Card::new(2, Suit::Hearts)];
+
+        let queens_full = PokerHandEvaluator::evaluate(&queens, &community_cards);
+        let nines_full = PokerHandEvaluator::evaluate(&nine_two, &community_cards);
+        assert_eq!(queens_full.primary_values, vec![13, 12]);
+        assert_eq!(nines_full.primary_values, vec![13, 9]);
+        assert!(queens_full > nines_full);
+    }
+
+    #[test]
+    fn test_four_of_a_kind_picks_highest_quads_and_kicker() {
+        let cards: Vec<Card> = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs]
+            .into_iter()
+            .flat_map(|suit| [Card::new(9, suit), Card::new(3, suit)])
+            .collect();
+
+        for _ in 0..20 {
+            let evaluated = PokerHandEvaluator::evaluate(&cards[..2], &cards[2..]);
+            assert_eq!(evaluated.rank, HandRank::FourOfAKind);
+            assert_eq!(evaluated.primary_values, vec![9]);
+            assert_eq!(evaluated.kickers, vec![3]);
+        }
+    }
+
+    #[test]
+    fn test_straight_picks_highest_window() {
+        let hole_cards = vec![Card::new(4, Suit::Spades), Card::new(9, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(5, Suit::Diamonds),
+            Card::new(6, Suit::Clubs),
+            Card::new(7, Suit::Spades),
+            Card::new(8, Suit::Hearts),
+            Card::new(13, Suit::Diamonds),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Straight);
+        assert_eq!(evaluated.primary_values, vec![9, 8, 7, 6, 5]);
+    }
+
+    #[test]
+    fn test_board_quads_kicker_from_best_remaining_card() {
+        let community_cards = vec![
+            Card::new(8, Suit::Spades),
+            Card::new(8, Suit::Hearts),
+            Card::new(8, Suit::Diamonds),
+            Card::new(8, Suit::Clubs),
+            Card::new(3, Suit::Spades),
+        ];
+        let king_high = vec![Card::new(13, Suit::Spades), Card::new(2, Suit::Hearts)];
+        let queen_high = vec![Card::new(12, Suit::Spades), Card::new(11, Suit::Hearts)];
+        let deuces = vec![Card::new(2, Suit::Spades), Card::new(2, Suit::Clubs)];
+
+        let king_kicker = PokerHandEvaluator::evaluate(&king_high, &community_cards);
+        let queen_kicker = PokerHandEvaluator::evaluate(&queen_high, &community_cards);
+        let board_kicker = PokerHandEvaluator::evaluate(&deuces, &community_cards);
+        assert_eq!(king_kicker.kickers, vec![13]);
+        assert_eq!(queen_kicker.kickers, vec![12]);
+        assert_eq!(board_kicker.kickers, vec![3]);
+        assert!(king_kicker > queen_kicker);
+        assert!(queen_kicker > board_kicker);
+
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.players[0].hole_cards = queen_high;
+        state.players[1].hole_cards = king_high;
+        state.community_cards = community_cards;
+        state.stage = GameStage::Showdown;
+        state.determine_winner();
+        assert_eq!(state.last_result.unwrap().winners, vec![1]);
+    }
+
+    #[test]
+    fn test_flush_tie_break_on_fifth_card() {
+        let community_cards = vec![
+            Card::new(14, Suit::Hearts),
+            Card::new(13, Suit::Hearts),
+            Card::new(12, Suit::Hearts),
+            Card::new(9, Suit::Hearts),
+            Card::new(2, Suit::Clubs),
+        ];
+        let four_high = vec![Card::new(4, Suit::Hearts), Card::new(2, Suit::Spades)];
+        let three_high = vec![Card::new(3, Suit::Hearts), Card::new(2, Suit::Diamonds)];
+
+        let better = PokerHandEvaluator::evaluate(&four_high, &community_cards);
+        let worse = PokerHandEvaluator::evaluate(&three_high, &community_cards);
+        assert_eq!(better.rank, HandRank::Flush);
+        assert_eq!(better.primary_values, vec![14, 13, 12, 9, 4]);
+        assert_eq!(worse.primary_values, vec![14, 13, 12, 9, 3]);
+        assert!(better > worse);
+    }
+
+    #[test]
+    fn test_flush_keeps_top_five_of_seven_suited() {
+        let hole_cards = vec![Card::new(3, Suit::Spades), Card::new(14, Suit::Spades)];
+        let community_cards = vec![
+            Card::new(5, Suit::Spades),
+            Card::new(11, Suit::Spades),
+            Card::new(8, Suit::Spades),
+            Card::new(2, Suit::Spades),
+            Card::new(13, Suit::Spades),
+        ];
+
+        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
+        assert_eq!(evaluated.rank, HandRank::Flush);
+        assert_eq!(evaluated.primary_values, vec![14, 13, 11, 8, 5]);
+        assert!(evaluated.kickers.is_empty());
+    }
+
+    #[test]
+    fn test_game_builder_custom_game() {
+        let mut state = GameBuilder::new()
+            .players(&[("A", 1000), ("B", 500)])
+            .dealer(1)
+            .deck(Deck::new())
+            .blinds(5, 10)
+            .build();
+
+        assert_eq!(state.players[0].get_name(), "A");
+        assert_eq!(state.players[1].get_chips(), 500);
+        assert_eq!(state.dealer_position, 1);
+
+        state.start_new_hand().unwrap();
+        assert_eq!(state.pot, 15);
+        assert_eq!(state.to_call, 10);
+        assert_eq!(state.players[0].get_current_bet(), 5);
+        assert_eq!(state.players[1].get_current_bet(), 10);
+        assert_eq!(
+            state.players[0].get_hole_cards(),
+            [Card::new(2, Suit::Spades), Card::new(3, Suit::Spades)]
+        );
+    }
+
+    #[test]
+    fn test_nut_hand_three_hearts_is_ace_high_flush() {
+        let community_cards = vec![
+            Card::new(13, Suit::Hearts),
+            Card::new(9, Suit::Hearts),
+            Card::new(4, Suit::Hearts),
+            Card::new(2, Suit::Clubs),
+            Card::new(7, Suit::Spades),
+        ];
+
+        let nuts = PokerHandEvaluator::nut_hand(&community_cards);
+        assert_eq!(nuts.rank, HandRank::Flush);
+        assert_eq!(nuts.primary_values, vec![14, 13, 12, 9, 4]);
+    }
+
+    #[test]
+    fn test_nut_hand_straight_flush_board() {
+        let community_cards = vec![
+            Card::new(9, Suit::Hearts),
+            Card::new(10, Suit::Hearts),
+            Card::new(11, Suit::Hearts),
+            Card::new(2, Suit::Clubs),
+            Card::new(3, Suit::Diamonds),
+        ];
+
+        let nuts = PokerHandEvaluator::nut_hand(&community_cards);
+        assert_eq!(nuts.rank, HandRank::StraightFlush);
+        assert_eq!(nuts.primary_values, vec![13, 12, 11, 10, 9]);
+    }
+
+    #[test]
+    fn test_hand_stats_tally() {
+        let mut stats = HandStats::new();
+        let pair = EvaluatedHand::new(HandRank::Pair, vec![5], vec![14, 13, 12]);
+        let flush = EvaluatedHand::new(HandRank::Flush, vec![14, 11, 9, 5, 2], Vec::new());
+
+        stats.record(&pair);
+        stats.record(&pair);
+        stats.record(&flush);
+        stats.record(&pair);
+
+        assert_eq!(stats.get_count(HandRank::Pair), 3);
+        assert_eq!(stats.get_count(HandRank::Flush), 1);
+        assert_eq!(stats.get_count(HandRank::RoyalFlush), 0);
+        assert_eq!(stats.get_total(), 4);
+        assert!((stats.frequency(HandRank::Pair) - 0.75).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_player_who_always_folds_has_zero_vpip() {
+        let mut state = PokerGameState::new();
+        for _ in 0..4 {
+            state.start_new_hand().unwrap();
+            if state.current_player == 0 {
+                state.perform_action(PlayerAction::Fold).unwrap();
+            } else {
+                state.perform_action(PlayerAction::Raise).unwrap();
+                state.perform_action(PlayerAction::Fold).unwrap();
+            }
+        }
+
+        let folder = state.players[0].get_stats();
+        assert_eq!(folder.hands_played, 4);
+        assert_eq!(folder.vpip_hands, 0);
+        assert!(folder.vpip().abs() < f64::EPSILON);
+        assert_eq!(folder.hands_won, 0);
+
+        let raiser = state.players[1].get_stats();
+        assert_eq!(raiser.hands_won, 4);
+        assert_eq!(raiser.pfr_hands, 2);
+        assert!(raiser.chips_won > 0);
+    }
+
+    #[test]
+    fn test_hand_history_text_for_simple_hand() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000)])
+            .deck(Deck::new())
+            .blinds(10, 20)
+            .build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+
+        let expected = "\
+Hand #1: Hold'em No Limit (10/20)
+Seat #1 is the button
+Seat 1: Alice (1000 in chips)
+Seat 2: Bob (1000 in chips)
+Bob: posts small blind 10
+Alice: posts big blind 20
+*** HOLE CARDS ***
+Bob: calls 10
+Alice: checks
+*** FLOP *** [6♠ 7♠ 8♠]
+Bob: folds
+Alice collected 40 from pot
+*** SUMMARY ***
+Total pot 40
+Board [6♠ 7♠ 8♠]
+Seat 1: Alice collected (40)";
+        assert_eq!(state.to_hand_history_text(), expected);
+    }
+
+    #[test]
+    fn test_hand_history_round_trip_replays_to_same_stacks() {
+        let builder = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000)])
+            .deck(Deck::new())
+            .blinds(10, 20);
+        let mut original = builder.clone().build();
+        original.start_new_hand().unwrap();
+        original.bet_amount = 70;
+        original.perform_action(PlayerAction::Raise).unwrap();
+        original.perform_action(PlayerAction::Call).unwrap();
+        original.perform_action(PlayerAction::Check).unwrap();
+        original.perform_action(PlayerAction::Check).unwrap();
+        original.bet_amount = 100;
+        original.perform_action(PlayerAction::Bet).unwrap();
+        original.perform_action(PlayerAction::Fold).unwrap();
+
+        let text = original.to_hand_history_text().replace('\n', "  \n  ");
+        let actions = parse_hand_history(&text).unwrap();
+        assert_eq!(actions.len(), 6);
+        assert_eq!(actions[0], (PlayerAction::Raise, 70));
+
+        let mut replay = builder.build();
+        replay.start_new_hand().unwrap();
+        for (action, amount) in actions {
+            replay.bet_amount = amount;
+            replay.perform_action(action).unwrap();
+        }
+        let stacks =
+            |s: &PokerGameState| s.players.iter().map(Player::get_chips).collect::<Vec<_>>();
+        assert_eq!(stacks(&replay), stacks(&original));
+        assert_eq!(
+            parse_hand_history("Alice: dances").unwrap_err().reason,
+            "Missing hole cards section"
+        );
+    }
+
+    #[test]
+    fn test_session_csv_has_row_per_hand() {
+        let mut runner = GameRunner::new(PokerGameState::new_seeded(7));
+        for _ in 0..5 {
+            runner.start_new_hand().unwrap();
+            runner.perform_action(PlayerAction::Fold).unwrap();
+        }
+
+        let csv = runner.session_csv();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0], "hand,Alice,Bob,pot");
+        assert_eq!(runner.get_hands().len(), 5);
+        assert!(rows[1].starts_with("1,"));
+        assert!(rows[5].ends_with(",20"));
+    }
+
+    #[test]
+    fn test_is_action_legal_facing_a_bet() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert!(!state.is_action_legal(PlayerAction::Check));
+        assert!(!state.is_action_legal(PlayerAction::Bet));
+        assert!(state.is_action_legal(PlayerAction::Call));
+        assert_eq!(
+            state.perform_action(PlayerAction::Check),
+            Err("Cannot check when a bet is pending")
+        );
+
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert!(state.is_action_legal(PlayerAction::Check));
+    }
+
+    #[test]
+    fn test_raise_to_sets_to_call_without_bet_amount() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let player = state.current_player;
+        state.bet_amount = 0;
+
+        state.perform_action(PlayerAction::RaiseTo(200)).unwrap();
+        assert_eq!(state.to_call, 200);
+        assert_eq!(state.players[player].get_current_bet(), 200);
+        assert_eq!(state.pot, 220);
+        assert!(!state.is_action_legal(PlayerAction::RaiseTo(150)));
+        assert!(!state.is_action_legal(PlayerAction::BetTo(300)));
+    }
+
+    #[test]
+    fn test_short_all_in_does_not_reopen_betting() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 150)])
+            .blinds(10, 20)
+            .build();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.current_player, 1);
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::RaiseTo(100)).unwrap();
+        assert_eq!(state.last_aggressor, Some(0));
+        assert_eq!(state.last_raise_size, 80);
+
+        state.perform_action(PlayerAction::AllIn).unwrap();
+        assert_eq!(state.to_call, 150);
+        assert_eq!(state.last_aggressor, Some(0));
+        assert_eq!(state.current_player, 0);
+        assert!(!state.can_raise());
+        assert!(!state.is_action_legal(PlayerAction::RaiseTo(400)));
+        assert!(!state.is_action_legal(PlayerAction::AllIn));
+        assert!(state.is_action_legal(PlayerAction::Call));
+    }
+
+    #[test]
+    fn test_board_stage_helpers_after_flop() {
+        let mut state = GameBuilder::new().deck(Deck::new()).build();
+        state.start_new_hand().unwrap();
+        assert!(state.flop().is_empty());
+
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        assert_eq!(state.stage, GameStage::Flop);
+        assert_eq!(state.flop().len(), 3);
+        assert_eq!(state.board_cards(), state.flop());
+        assert_eq!(state.turn(), None);
+        assert_eq!(state.river(), None);
+    }
+
+    #[test]
+    fn test_fold_and_next_rotates_dealer() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let mut dealer = state.dealer_position;
+
+        for hand in 2..5 {
+            state.fold_and_next().unwrap();
+            assert_eq!(state.hand_number, hand);
+            assert_eq!(state.stage, GameStage::Preflop);
+            assert_eq!(state.dealer_position, (dealer + 1) % 2);
+            dealer = state.dealer_position;
+        }
+    }
+
+    #[test]
+    fn test_raise_cap_blocks_fourth_raise() {
+        let mut state = PokerGameState::new();
+        state.max_raises_per_street = Some(3);
+        state.start_new_hand().unwrap();
+
+        state.perform_action(PlayerAction::RaiseTo(40)).unwrap();
+        state.perform_action(PlayerAction::RaiseTo(80)).unwrap();
+        state.perform_action(PlayerAction::RaiseTo(160)).unwrap();
+        assert_eq!(state.raises_this_street, 3);
+        assert_eq!(
+            state.perform_action(PlayerAction::RaiseTo(320)),
+            Err("Raise cap reached")
+        );
+        assert!(state.is_action_legal(PlayerAction::Call));
+    }
+
+    #[test]
+    fn test_deck_standard_completeness() {
+        let mut deck = Deck::new();
+        assert!(deck.is_standard_complete());
+        deck.shuffle();
+        assert!(deck.is_standard_complete());
+
+        deck.deal(1);
+        assert!(!deck.is_standard_complete());
+        assert!(!Deck::for_variant(Variant::ShortDeck).is_standard_complete());
+
+        let mut state = GameBuilder::new().deck(deck).build();
+        assert_eq!(
+            state.start_new_hand(),
+            Err("Deck is not a complete 52-card set")
+        );
+    }
+
+    #[test]
+    fn test_current_hand_top_pair_on_flop() {
+        let mut state = PokerGameState::new();
+        state.players[0].receive_cards(vec![
+            Card::new(13, Suit::Hearts),
+            Card::new(10, Suit::Clubs),
+        ]);
+        assert!(state.current_hand(0).is_none());
+
+        state.community_cards = vec![
+            Card::new(13, Suit::Spades),
+            Card::new(7, Suit::Diamonds),
+            Card::new(2, Suit::Clubs),
+        ];
+        let hand = state.current_hand(0).unwrap();
+        assert_eq!(hand.rank, HandRank::Pair);
+        assert_eq!(hand.primary_values, vec![13]);
+        assert!(state.current_hand(5).is_none());
+    }
+
+    #[test]
+    fn test_flush_and_straight_draws() {
+        let hole_cards = vec![Card::new(6, Suit::Hearts), Card::new(7, Suit::Hearts)];
+        let flop = vec![
+            Card::new(8, Suit::Hearts),
+            Card::new(9, Suit::Spades),
+            Card::new(2, Suit::Hearts),
+        ];
+        assert!(PokerHandEvaluator::has_flush_draw(&hole_cards, &flop));
+        assert!(PokerHandEvaluator::has_open_ended_straight_draw(
+            &hole_cards,
+            &flop
+        ));
+        assert!(!PokerHandEvaluator::has_gutshot(&hole_cards, &flop));
+        assert!(!PokerHandEvaluator::has_flush_draw(&hole_cards, &flop[..2]));
+
+        let gutshot_flop = vec![
+            Card::new(9, Suit::Spades),
+            Card::new(10, Suit::Clubs),
+            Card::new(2, Suit::Diamonds),
+        ];
+        assert!(PokerHandEvaluator::has_gutshot(&hole_cards, &gutshot_flop));
+        assert!(!PokerHandEvaluator::has_open_ended_straight_draw(
+            &hole_cards,
+            &gutshot_flop
+        ));
+        assert!(!PokerHandEvaluator::has_flush_draw(
+            &hole_cards,
+            &gutshot_flop
+        ));
+    }
+
+    #[test]
+    fn test_river_bettor_reveals_first() {
+        let mut state = GameBuilder::new().deck(Deck::new()).build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        state
+            .community_cards
+            .extend([Card::new(13, Suit::Hearts), Card::new(12, Suit::Diamonds)]);
+        state
+            .deck
+            .cards
+            .retain(|c| !state.community_cards.contains(c));
+        state.stage = GameStage::River;
+        assert_eq!(state.showdown_reveal_order(), vec![1, 0]);
+
+        state.perform_action(PlayerAction::Check).unwrap();
+        state.perform_action(PlayerAction::BetTo(120)).unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(state.stage, GameStage::HandComplete);
+        assert_eq!(state.last_aggressor, Some(0));
+        assert_eq!(state.showdown_reveal_order(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_run_it_twice_deals_disjoint_boards_and_splits_pot() {
+        let mut state = PokerGameState::new_seeded(11);
+        state.run_twice_on_all_in = true;
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::AllIn).unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(state.stage, GameStage::HandComplete);
+
+        let boards: Vec<&Vec<Card>> = state
+            .event_log
+            .iter()
+            .filter_map(|e| match e {
+                HandEvent::StreetDealt { cards, .. } => Some(cards),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(boards.len(), 2);
+        assert_eq!(boards[0].len(), 5);
+        assert!(boards[0].iter().all(|c| !boards[1].contains(c)));
+
+        let awarded: u64 = state
+            .event_log
+            .iter()
+            .filter_map(|e| match e {
+                HandEvent::PotAwarded { amount, .. } => Some(amount),
+                _ => None,
+            })
+            .sum();
+        assert_eq!(awarded, 2 * INITIAL_CHIPS);
+        assert_eq!(state.pot, 2 * INITIAL_CHIPS);
+        let combined = state.last_result.as_ref().unwrap();
+        assert_eq!(combined.amounts.iter().sum::<u64>(), state.pot);
+        assert_eq!(state.run_it_twice(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_advance_if_no_action_runs_out_all_in_hand() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.advance_if_no_action().unwrap();
+        assert_eq!(state.stage, GameStage::Preflop);
+
+        for player in &mut state.players {
+            let chips = player.get_chips();
+            player.bet(chips).unwrap();
+            state.pot += chips;
+        }
+        state.advance_if_no_action().unwrap();
+
+        assert_eq!(state.stage, GameStage::HandComplete);
+        assert_eq!(state.community_cards.len(), 5);
+        let result = state.last_result.as_ref().unwrap();
+        assert_eq!(result.reason, WinReason::Showdown);
+        assert_eq!(result.amounts.iter().sum::<u64>(), 2 * INITIAL_CHIPS);
+    }
+
+    #[test]
+    fn test_rank_only_sort_and_suit_grouping() {
+        let mut cards = vec![
+            Card::new(9, Suit::Clubs),
+            Card::new(4, Suit::Hearts),
+            Card::new(9, Suit::Spades),
+            Card::new(14, Suit::Hearts),
+            Card::new(4, Suit::Diamonds),
+        ];
+        cards.sort_by(Card::cmp_by_rank);
+        assert_eq!(
+            cards,
+            vec![
+                Card::new(4, Suit::Hearts),
+                Card::new(4, Suit::Diamonds),
+                Card::new(9, Suit::Clubs),
+                Card::new(9, Suit::Spades),
+                Card::new(14, Suit::Hearts),
+            ]
+        );
+
+        let groups = Card::group_by_suit(&cards);
+        assert_eq!(groups[&Suit::Hearts], vec![4, 14]);
+        assert_eq!(groups[&Suit::Clubs], vec![9]);
+        assert_eq!(groups.len(), 4);
+    }
+
+    #[test]
+    fn test_set_board_validation() {
+        let mut state = PokerGameState::new();
+        state.players[0].receive_cards(vec![
+            Card::new(14, Suit::Spades),
+            Card::new(13, Suit::Spades),
+        ]);
+        let board: Vec<Card> = (2..8).map(|rank| Card::new(rank, Suit::Hearts)).collect();
+
+        assert_eq!(
+            state.set_board(board.clone()),
+            Err("Board cannot have more than 5 cards")
+        );
+        assert_eq!(
+            state.set_board(vec![Card::new(14, Suit::Spades)]),
+            Err("Duplicate card on board")
+        );
+        state.set_board(board[..5].to_vec()).unwrap();
+        assert_eq!(state.board_cards(), &board[..5]);
+    }
+
+    #[test]
+    fn test_evaluate_fast_orders_like_evaluate() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut hands: Vec<[Card; 7]> = Vec::new();
+        for _ in 0..3000 {
+            let mut deck = Deck::new();
+            deck.shuffle_with_rng(&mut rng);
+            let cards = deck.deal(7).unwrap();
+            hands.push(cards.try_into().unwrap());
+        }
+        let wheel = [
+            (14, Suit::Spades),
+            (2, Suit::Hearts),
+            (3, Suit::Clubs),
+            (4, Suit::Spades),
+            (5, Suit::Diamonds),
+            (9, Suit::Hearts),
+            (13, Suit::Clubs),
+        ];
+        let royal = [
+            (10, Suit::Hearts),
+            (11, Suit::Hearts),
+            (12, Suit::Hearts),
+            (13, Suit::Hearts),
+            (14, Suit::Hearts),
+            (2, Suit::Clubs),
+            (2, Suit::Spades),
+        ];
+        for fixed in [wheel, royal] {
+            hands.push(fixed.map(|(rank, suit)| Card::new(rank, suit)));
+        }
+
+        let slow: Vec<EvaluatedHand> = hands
+            .iter()
+            .map(|h| PokerHandEvaluator::evaluate(&h[..2], &h[2..]))
+            .collect();
+        let fast: Vec<u32> = hands
+            .iter()
+            .map(PokerHandEvaluator::evaluate_fast)
+            .collect();
+        for i in 1..hands.len() {
+            for j in [i - 1, i / 2] {
+                assert_eq!(
+                    fast[i].cmp(&fast[j]),
+                    slow[i].cmp(&slow[j]),
+                    "{:?} vs {:?}",
+                    hands[i],
+                    hands[j]
+                );
+            }
+        }
+        assert_eq!(fast[fast.len() - 1] >> 20, HandRank::RoyalFlush as u32);
+    }
+
+    #[test]
+    fn test_evaluate_matches_fast_over_100k_hands() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut deck = Deck::new();
+        for _ in 0..100_000 {
+            deck.shuffle_with_rng(&mut rng);
+            let cards: [Card; 7] = deck.cards[..7].try_into().unwrap();
+            let hand = PokerHandEvaluator::evaluate(&cards[..2], &cards[2..]);
+            let packed = hand
+                .primary_values
+                .iter()
+                .chain(&hand.kickers)
+                .chain(&[0; 5])
+                .take(5)
+                .fold(hand.rank as u32, |acc, &v| (acc << 4) | u32::from(v));
+            assert_eq!(
+                packed,
+                PokerHandEvaluator::evaluate_fast(&cards),
+                "{cards:?}"
+            );
+        }
+    }
+
+    /// Times the old `HashMap` rank and suit counts against the fixed arrays
+    /// `evaluate` now uses, plus `evaluate` itself, over 100k seven-card hands.
+    /// Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing only"]
+    fn bench_rank_histograms_100k_hands() {
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut deck = Deck::new();
+        let hands: Vec<[Card; 7]> = (0..100_000)
+            .map(|_| {
+                deck.shuffle_with_rng(&mut rng);
+                deck.cards[..7].try_into().unwrap()
+            })
+            .collect();
+
+        let start = Instant::now();
+        for cards in &hands {
+            let mut ranks: HashMap<u8, usize> = HashMap::new();
+            let mut suits: HashMap<Suit, usize> = HashMap::new();
+            for card in cards {
+                *ranks.entry(card.rank).or_insert(0) += 1;
+                *suits.entry(card.suit).or_insert(0) += 1;
+            }
+            black_box((ranks, suits));
+        }
+        let before = start.elapsed();
+
+        let start = Instant::now();
+        for cards in &hands {
+            let mut ranks = [0u8; 15];
+            let mut suits = [0u8; 4];
+            for card in cards {
+                ranks[usize::from(card.rank)] += 1;
+                suits[card.suit as usize] += 1;
+            }
+            black_box((ranks, suits));
+        }
+        let after = start.elapsed();
+
+        let start = Instant::now();
+        for cards in &hands {
+            black_box(PokerHandEvaluator::evaluate(&cards[..2], &cards[2..]));
+        }
+        let evaluate = start.elapsed();
+
+        println!("HashMap counts: {before:?}, array counts: {after:?}, evaluate: {evaluate:?}");
+    }
+
+    #[test]
+    fn test_batch_equity_aces_beat_kings() {
+        let aces = vec![Card::new(14, Suit::Spades), Card::new(14, Suit::Hearts)];
+        let kings = vec![Card::new(13, Suit::Spades), Card::new(13, Suit::Hearts)];
+        let suited = vec![Card::new(7, Suit::Clubs), Card::new(8, Suit::Clubs)];
+        let matchups = vec![
+            (aces.clone(), kings.clone()),
+            (kings, aces.clone()),
+            (suited, aces),
+        ];
+
+        let equities = batch_equity(&matchups, &[], 2000);
+        assert_eq!(equities.len(), 3);
+        assert!(equities.iter().all(|e| (0.0..=1.0).contains(e)));
+        assert!(equities[0] > 0.7);
+        assert!(equities[1] < 0.3);
+    }
+
+    #[test]
+    fn test_card_mask_round_trip() {
+        let deck = Deck::new();
+        let full = cards_to_mask(&deck.cards);
+        assert_eq!(full.count_ones(), 52);
+        assert_eq!(mask_to_cards(full), deck.cards);
+
+        let hero = vec![Card::new(14, Suit::Spades), Card::new(10, Suit::Hearts)];
+        let villain = vec![Card::new(14, Suit::Clubs), Card::new(2, Suit::Diamonds)];
+        assert_eq!(mask_to_cards(cards_to_mask(&hero)), hero);
+        assert_eq!(cards_to_mask(&hero) & cards_to_mask(&villain), 0);
+        assert_ne!(
+            cards_to_mask(&hero) & Card::new(10, Suit::Hearts).to_bit(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_deck_reset_reuses_allocation() {
+        let mut deck = Deck::new();
+        let capacity = deck.cards.capacity();
+        deck.deal(9);
+        deck.burn();
+
+        deck.reset();
+        assert_eq!(deck.len(), 52);
+        assert!(deck.is_standard_complete());
+        assert_eq!(deck.cards.capacity(), capacity);
+
+        deck.reset_and_shuffle(&mut StdRng::seed_from_u64(3));
+        assert!(deck.is_standard_complete());
+        assert_eq!(deck.cards.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_dealing_without_burn_cards() {
+        let mut state = GameBuilder::new().deck(Deck::new()).build();
+        state.burn_cards = false;
+        state.start_new_hand().unwrap();
+        assert_eq!(state.deck.len(), 48);
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+
+        assert_eq!(state.deck.len(), 45);
+        assert_eq!(state.board_cards(), &Deck::new().cards[4..7]);
+        assert!(!state.dealing_plan().contains(&DealStep::Burn));
+    }
+
+    #[test]
+    fn test_hero_equity_with_made_flush_is_high() {
+        let mut state = PokerGameState::new();
+        state.players[0].receive_cards(vec![
+            Card::new(14, Suit::Hearts),
+            Card::new(13, Suit::Hearts),
+        ]);
+        state
+            .set_board(vec![
+                Card::new(2, Suit::Hearts),
+                Card::new(7, Suit::Hearts),
+                Card::new(9, Suit::Hearts),
+            ])
+            .unwrap();
+
+        let equity = state.hero_equity(0, 500);
+        assert!(equity > 0.8, "equity {equity}");
+        assert!(equity <= 1.0);
+        assert!(state.hero_equity(1, 100).abs() < f64::EPSILON);
     }
 
-    fn get_active_players(&self) -> Vec<usize> {
-        self.players
-            .iter()
-            .enumerate()
-            .filter(|(_, p)| !p.is_folded())
-            .map(|(i, _)| i)
-            .collect()
+    #[test]
+    fn test_street_bets_do_not_carry_to_next_street() {
+        let mut state = GameBuilder::new().deck(Deck::new()).build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::RaiseTo(100)).unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(state.stage, GameStage::Flop);
+        assert!(state.players.iter().all(|p| p.street_contribution() == 0));
+
+        state.perform_action(PlayerAction::Check).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        assert_eq!(state.stage, GameStage::Turn);
+        assert_eq!(state.pot, 200);
     }
 
-    fn get_betting_players(&self) -> Vec<usize> {
-        self.players
-            .iter()
-            .enumerate()
-            .filter(|(_, p)| !p.is_folded() && !p.is_all_in())
-            .map(|(i, _)| i)
-            .collect()
+    #[test]
+    fn test_flop_bet_charged_correctly_after_preflop_raise() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000)])
+            .deck(Deck::new())
+            .build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::RaiseTo(100)).unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+
+        assert_eq!(state.current_player, 1);
+        state.perform_action(PlayerAction::BetTo(60)).unwrap();
+        assert_eq!(state.to_call, 60);
+        assert_eq!(state.get_call_amount(), 60);
+        state.perform_action(PlayerAction::Call).unwrap();
+
+        assert_eq!(state.players[0].get_chips(), 840);
+        assert_eq!(state.players[1].get_chips(), 840);
+        assert_eq!(state.pot, 320);
+        assert_eq!(state.stage, GameStage::Turn);
     }
 
-    fn check_street_complete(&mut self) {
-        let active_players = self.get_active_players();
+    #[test]
+    fn test_total_invested_sums_streets() {
+        let mut state = GameBuilder::new().deck(Deck::new()).build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::RaiseTo(100)).unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::BetTo(60)).unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::BetTo(40)).unwrap();
+
+        let bettor = &state.players[1];
+        assert_eq!(bettor.get_current_bet(), 40);
+        assert_eq!(bettor.get_total_invested(), 200);
+        assert_eq!(state.players[0].get_total_invested(), 160);
+        let invested: u64 = state.players.iter().map(Player::get_total_invested).sum();
+        assert_eq!(invested, state.pot);
+    }
 
-        if active_players.len() == 1 {
-            let winner_idx = active_players[0];
-            self.players[winner_idx].collect_pot(self.pot);
-            self.end_hand();
-            return;
-        }
+    #[test]
+    fn test_short_all_in_can_win_less_than_pot() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 150), ("Carol", 1000)])
+            .build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::RaiseTo(500)).unwrap();
+        state.perform_action(PlayerAction::AllIn).unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(state.stage, GameStage::Flop);
+
+        state.perform_action(PlayerAction::Check).unwrap();
+        state.perform_action(PlayerAction::BetTo(200)).unwrap();
+        assert_eq!(state.pot, 1350);
+        // Alice's flop bet is not called yet, so it is in no pot anyone can win.
+        assert_eq!(state.max_winnable(1), 450);
+        assert_eq!(state.max_winnable(0), 1150);
+        assert_eq!(
+            state.side_pots(),
+            vec![
+                SidePot {
+                    amount: 450,
+                    eligible: vec![0, 1, 2]
+                },
+                SidePot {
+                    amount: 700,
+                    eligible: vec![0, 2]
+                },
+            ]
+        );
+    }
 
-        let betting_players = self.get_betting_players();
+    #[test]
+    fn test_wheel_can_be_disallowed() {
+        let hole_cards = vec![Card::new(14, Suit::Spades), Card::new(2, Suit::Hearts)];
+        let community_cards = vec![
+            Card::new(3, Suit::Clubs),
+            Card::new(4, Suit::Diamonds),
+            Card::new(5, Suit::Spades),
+            Card::new(9, Suit::Hearts),
+            Card::new(12, Suit::Clubs),
+        ];
 
-        if betting_players.is_empty() {
-            self.run_out_board();
-            self.determine_winner();
-            return;
-        }
+        let allowed = PokerHandEvaluator::evaluate_with_wheel(
+            &hole_cards,
+            &community_cards,
+            Variant::TexasHoldem,
+            true,
+        );
+        assert_eq!(allowed.rank, HandRank::Straight);
+        assert_eq!(allowed.primary_values, vec![5, 4, 3, 2, 1]);
+
+        let disallowed = PokerHandEvaluator::evaluate_with_wheel(
+            &hole_cards,
+            &community_cards,
+            Variant::TexasHoldem,
+            false,
+        );
+        assert_eq!(disallowed.rank, HandRank::HighCard);
+        assert_eq!(disallowed.kickers, vec![14, 12, 9, 5, 4]);
+    }
 
-        let all_acted = betting_players.iter().all(|&i| self.players[i].has_acted());
+    #[test]
+    fn test_best_five_flush_is_same_suit() {
+        let hole_cards = vec![Card::new(14, Suit::Diamonds), Card::new(9, Suit::Clubs)];
+        let community_cards = vec![
+            Card::new(3, Suit::Diamonds),
+            Card::new(11, Suit::Diamonds),
+            Card::new(9, Suit::Diamonds),
+            Card::new(9, Suit::Spades),
+            Card::new(6, Suit::Diamonds),
+        ];
 
-        let bets_equal = betting_players
-            .iter()
-            .all(|&i| self.players[i].get_current_bet() == self.to_call);
+        let five = PokerHandEvaluator::best_five(&hole_cards, &community_cards);
+        assert_eq!(five.len(), 5);
+        assert!(five.iter().all(|c| c.suit == Suit::Diamonds));
+        assert_eq!(
+            five.iter().map(|c| c.rank).collect::<Vec<_>>(),
+            vec![14, 11, 9, 6, 3]
+        );
+    }
 
-        if all_acted && bets_equal {
-            self.advance_street();
+    #[test]
+    fn test_winning_cards_for_made_straight() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert!(state.winning_cards().is_empty());
+
+        state.players[0].hole_cards = vec![Card::new(9, Suit::Hearts), Card::new(10, Suit::Clubs)];
+        state.players[1].hole_cards = vec![Card::new(2, Suit::Diamonds), Card::new(2, Suit::Clubs)];
+        state
+            .set_board(vec![
+                Card::new(6, Suit::Spades),
+                Card::new(7, Suit::Diamonds),
+                Card::new(8, Suit::Clubs),
+                Card::new(13, Suit::Hearts),
+                Card::new(3, Suit::Spades),
+            ])
+            .unwrap();
+        for player in &mut state.players {
+            let chips = player.get_chips();
+            player.bet(chips).unwrap();
+            state.pot += chips;
         }
+        state.advance_if_no_action().unwrap();
+
+        let cards = state.winning_cards();
+        assert_eq!(state.last_result.as_ref().unwrap().winners, vec![0]);
+        assert_eq!(
+            cards.iter().map(|c| c.rank).collect::<Vec<_>>(),
+            vec![10, 9, 8, 7, 6]
+        );
     }
 
-    fn run_out_board(&mut self) {
-        while self.community_cards.len() < 5 {
-            match self.stage {
-                GameStage::Preflop => {
-                    self.deal_community_cards(3);
-                    self.stage = GameStage::Flop;
-                }
-                GameStage::Flop => {
-                    self.deal_community_cards(1);
-                    self.stage = GameStage::Turn;
-                }
-                GameStage::Turn => {
-                    self.deal_community_cards(1);
-                    self.stage = GameStage::River;
+    #[test]
+    fn test_seeded_sessions_replay_bot_decisions() {
+        fn play_session(seed: u64) -> Vec<PlayerAction> {
+            let mut runner = GameRunner::new(PokerGameState::new_seeded(seed));
+            let mut decisions = Vec::new();
+            for _ in 0..5 {
+                runner.start_new_hand().unwrap();
+                while runner.state.stage != GameStage::HandComplete {
+                    let action = if runner.decision_rng().gen_bool(0.2) {
+                        PlayerAction::Fold
+                    } else if runner.state.is_action_legal(PlayerAction::Check) {
+                        PlayerAction::Check
+                    } else {
+                        PlayerAction::Call
+                    };
+                    runner.perform_action(action).unwrap();
+                    decisions.push(action);
                 }
-                _ => break,
             }
+            decisions
         }
-        self.stage = GameStage::Showdown;
+
+        let first = play_session(21);
+        assert_eq!(first, play_session(21));
+        assert!(first.contains(&PlayerAction::Fold));
+        assert_ne!(first, play_session(22));
     }
 
-    fn advance_street(&mut self) {
-        for player in &mut self.players {
-            player.set_has_acted(false);
-        }
+    #[test]
+    fn test_straddle_doubles_big_blind_and_moves_first_action() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.current_player, 1);
+        assert_eq!(
+            state.post_straddle(1, 40),
+            Err("Straddle only allowed before the deal")
+        );
+        state.fold_and_next().unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+
+        assert_eq!(state.dealer_position, 0);
+        assert_eq!(
+            state.post_straddle(1, 20),
+            Err("Straddle must exceed the big blind")
+        );
+        state.post_straddle(1, 2 * BIG_BLIND_CHIPS).unwrap();
+        state.start_new_hand().unwrap();
+
+        assert_eq!(state.to_call, 2 * BIG_BLIND_CHIPS);
+        assert_eq!(state.players[1].get_current_bet(), 2 * BIG_BLIND_CHIPS);
+        assert_eq!(state.pot, 60);
+        assert_eq!(state.current_player, 0);
+        assert_eq!(state.pending_straddle, None);
+    }
 
-        self.to_call = 0;
+    #[test]
+    fn test_uncalled_over_bet_returned_before_pot_is_awarded() {
+        let mut state = PokerGameState::new();
+        state
+            .players
+            .push(Player::new("Carol".to_string(), INITIAL_CHIPS));
+        state.players[1].chips = 400;
+        state.start_new_hand().unwrap();
+        state.players[0].hole_cards = vec![Card::new(7, Suit::Clubs), Card::new(2, Suit::Diamonds)];
+        state.players[1].hole_cards =
+            vec![Card::new(14, Suit::Spades), Card::new(14, Suit::Hearts)];
+        state.players[2].hole_cards = vec![Card::new(4, Suit::Clubs), Card::new(6, Suit::Hearts)];
+        state.community_cards = vec![
+            Card::new(3, Suit::Spades),
+            Card::new(9, Suit::Hearts),
+            Card::new(11, Suit::Diamonds),
+            Card::new(13, Suit::Clubs),
+            Card::new(5, Suit::Spades),
+        ];
+        let placed: Vec<Card> = state
+            .players
+            .iter()
+            .flat_map(|p| p.hole_cards.clone())
+            .chain(state.community_cards.clone())
+            .collect();
+        state.deck.cards.retain(|c| !placed.contains(c));
+
+        assert_eq!(state.current_player, 0);
+        state.perform_action(PlayerAction::AllIn).unwrap();
+        state.perform_action(PlayerAction::AllIn).unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+
+        assert_eq!(state.stage, GameStage::HandComplete);
+        let returned = INITIAL_CHIPS - 400;
+        assert!(state.event_log.contains(&HandEvent::UncalledBetReturned {
+            player: 0,
+            amount: returned,
+        }));
+        assert_eq!(state.pot, 400 + 400 + BIG_BLIND_CHIPS);
+        assert_eq!(state.last_result.as_ref().unwrap().amounts, vec![state.pot]);
+        assert_eq!(state.players[0].get_chips(), returned);
+        assert_eq!(state.players[1].get_chips(), state.pot);
+    }
 
-        match self.stage {
-            GameStage::Preflop => {
-                self.deal_community_cards(3);
-                self.stage = GameStage::Flop;
-            }
-            GameStage::Flop => {
-                self.deal_community_cards(1);
-                self.stage = GameStage::Turn;
+    #[test]
+    fn test_observer_sees_full_hand() {
+        #[derive(Default)]
+        struct Recorder(Vec<String>);
+        impl GameObserver for Recorder {
+            fn on_hand_start(&mut self, hand_number: u64) {
+                self.0.push(format!("hand {hand_number}"));
             }
-            GameStage::Turn => {
-                self.deal_community_cards(1);
-                self.stage = GameStage::River;
+            fn on_action(&mut self, player: usize, action: PlayerAction, amount: u64) {
+                self.0.push(format!("{player} {action:?} {amount}"));
             }
-            GameStage::River => {
-                self.stage = GameStage::Showdown;
-                self.determine_winner();
+            fn on_street(&mut self, stage: GameStage, cards: &[Card]) {
+                self.0.push(format!("{stage:?} {}", cards.len()));
             }
-            _ => {
-                self.end_hand();
+            fn on_showdown(&mut self, result: &HandResult) {
+                self.0
+                    .push(format!("{:?} {:?}", result.reason, result.amounts));
             }
         }
 
-        self.current_player = (self.dealer_position + 1) % self.players.len();
-        self.pending_action = true;
-        self.update_action_bounds();
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+        let mut state = PokerGameState::new_seeded(3);
+        state.add_observer(recorder.clone());
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        while state.stage != GameStage::HandComplete {
+            state.perform_action(PlayerAction::Check).unwrap();
+        }
+
+        let events = &recorder.borrow().0;
+        assert_eq!(events[0], "hand 1");
+        assert_eq!(events[1], "1 Call 10");
+        assert_eq!(events[2], "0 Check 0");
+        assert_eq!(events[3], "Flop 3");
+        assert!(events.contains(&"Turn 1".to_string()));
+        assert!(events.contains(&"River 1".to_string()));
+        assert!(events.last().unwrap().starts_with("Showdown"));
+        assert_eq!(events.len(), 3 + 1 + 2 + 1 + 2 + 1 + 2 + 1);
     }
 
-    fn deal_community_cards(&mut self, count: usize) {
-        self.deck.burn();
-        if let Some(cards) = self.deck.deal(count) {
-            self.community_cards.extend(cards);
-        }
+    #[test]
+    fn test_empty_state_has_ordered_deck() {
+        let state = PokerGameState::empty();
+
+        assert_eq!(state.deck.cards.len(), 52);
+        assert_eq!(state.deck.cards, Deck::new().cards);
+        assert_eq!(state.deck.cards[0], Card::new(2, Suit::ALL[0]));
+        assert_eq!(state.players.len(), NUM_PLAYERS);
+        assert_eq!(state.stage, GameStage::WaitingToStart);
     }
 
-    fn determine_winner(&mut self) {
-        let active_players = self.get_active_players();
+    #[test]
+    fn test_check_fold_auto_action() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.set_auto_action(0, AutoAction::CheckFold).unwrap();
+        assert_eq!(
+            state.players[0].get_auto_action(),
+            Some(AutoAction::CheckFold)
+        );
+
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(state.stage, GameStage::Flop);
+        assert_eq!(state.players[0].get_auto_action(), None);
+        assert!(state.event_log.contains(&HandEvent::Action {
+            player: 0,
+            stage: GameStage::Preflop,
+            action: PlayerAction::Check,
+            amount: 0,
+            total_bet: BIG_BLIND_CHIPS,
+        }));
+
+        state.set_auto_action(0, AutoAction::CheckFold).unwrap();
+        assert_eq!(state.current_player, 1);
+        state.perform_action(PlayerAction::Bet).unwrap();
+        assert!(state.players[0].is_folded());
+        assert_eq!(state.stage, GameStage::HandComplete);
+    }
 
-        if active_players.len() == 1 {
-            let winner_idx = active_players[0];
-            let winnings = self.pot;
-            self.players[winner_idx].collect_pot(winnings);
-            self.end_hand();
-            return;
+    #[test]
+    fn test_betting_round_and_open_across_stages() {
+        let mut state = PokerGameState::new();
+        assert_eq!(state.betting_round(), 0);
+        assert!(!state.is_betting_open());
+
+        state.start_new_hand().unwrap();
+        assert_eq!(state.betting_round(), 0);
+        assert!(state.is_betting_open());
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+
+        for round in 1..=3 {
+            assert_eq!(state.betting_round(), round);
+            assert!(state.is_betting_open());
+            state.perform_action(PlayerAction::Check).unwrap();
+            state.perform_action(PlayerAction::Check).unwrap();
         }
+        assert_eq!(state.stage, GameStage::HandComplete);
+        assert_eq!(state.betting_round(), 4);
+        assert!(!state.is_betting_open());
+
+        state.max_raises_per_street = Some(1);
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Raise).unwrap();
+        assert!(!state.is_betting_open());
+    }
 
-        let mut best_hand: Option<EvaluatedHand> = None;
-        let mut winners: Vec<usize> = Vec::new();
+    #[test]
+    fn test_sitting_out_player_is_dealt_around() {
+        let mut state = PokerGameState::new();
+        state
+            .players
+            .push(Player::new("Carol".to_string(), INITIAL_CHIPS));
+        state.players[2].sit_out(true);
+        state.owe_missed_blinds = true;
+        state.start_new_hand().unwrap();
+
+        assert!(state.players[2].get_hole_cards().is_empty());
+        assert!(state.players[2].is_folded());
+        assert_eq!(state.players[2].get_current_bet(), 0);
+        assert_eq!(state.players[1].get_current_bet(), SMALL_BLIND_CHIPS);
+        assert_eq!(state.players[0].get_current_bet(), BIG_BLIND_CHIPS);
+        assert_eq!(state.current_player, 1);
+        assert!(state.players[2].owes_blind());
+
+        state.perform_action(PlayerAction::Fold).unwrap();
+        assert_eq!(state.stage, GameStage::HandComplete);
+        assert_eq!(state.players[2].get_stats().hands_played, 0);
+
+        state.players[2].sit_out(false);
+        state.dealer_position = 2;
+        state.start_new_hand().unwrap();
+        assert_eq!(state.players[2].get_hole_cards().len(), 2);
+        assert_eq!(state.players[2].get_current_bet(), BIG_BLIND_CHIPS);
+        assert!(!state.players[2].owes_blind());
+        assert_eq!(state.pot, SMALL_BLIND_CHIPS + 2 * BIG_BLIND_CHIPS);
+    }
 
-        for &player_idx in &active_players {
-            let hand = PokerHandEvaluator::evaluate(
-                self.players[player_idx].get_hole_cards(),
-                &self.community_cards,
-            );
+    #[test]
+    fn test_default_bet_is_legal_on_new_street() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.bet_amount, state.min_bet);
+        let max_bet = state.max_bet;
+        state.set_bet_amount(max_bet);
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+
+        assert_eq!(state.stage, GameStage::Flop);
+        assert_eq!(state.bet_amount, BIG_BLIND_CHIPS);
+        assert!(state.bet_amount >= state.min_bet && state.bet_amount <= state.max_bet);
+        assert!(state.is_action_legal(PlayerAction::Bet));
+        state.perform_action(PlayerAction::Bet).unwrap();
+        assert_eq!(state.to_call, BIG_BLIND_CHIPS);
+    }
 
-            match best_hand.as_ref() {
-                Some(best) => match hand.cmp(best) {
-                    std::cmp::Ordering::Greater => {
-                        best_hand = Some(hand);
-                        winners.clear();
-                        winners.push(player_idx);
-                    }
-                    std::cmp::Ordering::Equal => {
-                        winners.push(player_idx);
-                    }
-                    std::cmp::Ordering::Less => {}
-                },
-                None => {
-                    best_hand = Some(hand);
-                    winners.push(player_idx);
-                }
-            }
+    #[test]
+    fn test_min_raise_to_is_call_plus_last_raise() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.min_raise_to(), BIG_BLIND_CHIPS + BIG_BLIND_CHIPS);
+        assert_eq!(state.max_raise_to(), INITIAL_CHIPS);
+
+        state.perform_action(PlayerAction::RaiseTo(70)).unwrap();
+        assert_eq!(state.last_raise_size, 50);
+        assert_eq!(state.min_raise_to(), 70 + 50);
+        assert_eq!(state.min_raise_to(), state.to_call + state.last_raise_size);
+
+        state.players[0].chips = 80;
+        assert_eq!(state.max_raise_to(), BIG_BLIND_CHIPS + 80);
+        assert_eq!(state.min_raise_to(), BIG_BLIND_CHIPS + 80);
+    }
+
+    #[test]
+    fn test_qualifies_jacks_or_better() {
+        let five = |cards: [(u8, Suit); 5]| {
+            let cards: Vec<Card> = cards.iter().map(|&(r, s)| Card::new(r, s)).collect();
+            PokerHandEvaluator::evaluate(&cards[..2], &cards[2..])
+        };
+
+        let tens = five([
+            (10, Suit::Spades),
+            (10, Suit::Hearts),
+            (14, Suit::Clubs),
+            (7, Suit::Diamonds),
+            (3, Suit::Spades),
+        ]);
+        assert_eq!(tens.rank, HandRank::Pair);
+        assert!(!tens.qualifies_jacks_or_better());
+
+        let jacks = five([
+            (11, Suit::Spades),
+            (11, Suit::Hearts),
+            (4, Suit::Clubs),
+            (7, Suit::Diamonds),
+            (3, Suit::Spades),
+        ]);
+        assert!(jacks.qualifies_jacks_or_better());
+
+        let two_pair = five([
+            (2, Suit::Spades),
+            (2, Suit::Hearts),
+            (3, Suit::Clubs),
+            (3, Suit::Diamonds),
+            (9, Suit::Spades),
+        ]);
+        assert_eq!(two_pair.rank, HandRank::TwoPair);
+        assert!(two_pair.qualifies_jacks_or_better());
+    }
+
+    #[test]
+    fn test_deuce_to_seven_low() {
+        let cards = |cards: &[(u8, Suit)]| -> Vec<Card> {
+            cards.iter().map(|&(r, s)| Card::new(r, s)).collect()
+        };
+        let nuts = PokerHandEvaluator::evaluate_low_2to7(&cards(&[
+            (7, Suit::Spades),
+            (5, Suit::Hearts),
+            (4, Suit::Clubs),
+            (3, Suit::Diamonds),
+            (2, Suit::Spades),
+        ]))
+        .unwrap();
+        let pair = PokerHandEvaluator::evaluate_low_2to7(&cards(&[
+            (2, Suit::Hearts),
+            (2, Suit::Spades),
+            (3, Suit::Clubs),
+            (4, Suit::Diamonds),
+            (5, Suit::Spades),
+        ]))
+        .unwrap();
+        let wheel = PokerHandEvaluator::evaluate_low_2to7(&cards(&[
+            (14, Suit::Hearts),
+            (2, Suit::Spades),
+            (3, Suit::Clubs),
+            (4, Suit::Diamonds),
+            (5, Suit::Spades),
+        ]))
+        .unwrap();
+        let straight = PokerHandEvaluator::evaluate_low_2to7(&cards(&[
+            (6, Suit::Hearts),
+            (2, Suit::Spades),
+            (3, Suit::Clubs),
+            (4, Suit::Diamonds),
+            (5, Suit::Spades),
+        ]))
+        .unwrap();
+        let flush = PokerHandEvaluator::evaluate_low_2to7(&cards(&[
+            (7, Suit::Spades),
+            (5, Suit::Spades),
+            (4, Suit::Spades),
+            (3, Suit::Spades),
+            (2, Suit::Spades),
+        ]))
+        .unwrap();
+
+        assert_eq!(nuts.rank, HandRank::HighCard);
+        assert!(pair > nuts);
+        assert_eq!(wheel.rank, HandRank::HighCard);
+        assert!(wheel > nuts);
+        assert!(straight > pair);
+        assert!(flush > nuts);
+
+        let seven_cards = cards(&[
+            (13, Suit::Spades),
+            (7, Suit::Spades),
+            (5, Suit::Hearts),
+            (4, Suit::Clubs),
+            (3, Suit::Diamonds),
+            (2, Suit::Spades),
+            (2, Suit::Hearts),
+        ]);
+        assert_eq!(
+            PokerHandEvaluator::evaluate_low_2to7(&seven_cards),
+            Some(nuts)
+        );
+        assert_eq!(
+            PokerHandEvaluator::evaluate_low_2to7(&seven_cards[..4]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_eight_or_better_low() {
+        let cards = |cards: &[(u8, Suit)]| -> Vec<Card> {
+            cards.iter().map(|&(r, s)| Card::new(r, s)).collect()
+        };
+        let wheel = PokerHandEvaluator::evaluate_low_8(&cards(&[
+            (14, Suit::Hearts),
+            (2, Suit::Hearts),
+            (3, Suit::Hearts),
+            (4, Suit::Hearts),
+            (5, Suit::Hearts),
+        ]))
+        .unwrap();
+        assert_eq!(wheel.primary_values, vec![5, 4, 3, 2, 1]);
+
+        let eight = PokerHandEvaluator::evaluate_low_8(&cards(&[
+            (8, Suit::Spades),
+            (7, Suit::Hearts),
+            (3, Suit::Clubs),
+            (2, Suit::Diamonds),
+            (14, Suit::Spades),
+            (2, Suit::Hearts),
+            (13, Suit::Clubs),
+        ]))
+        .unwrap();
+        assert!(wheel < eight);
+
+        assert_eq!(
+            PokerHandEvaluator::evaluate_low_8(&cards(&[
+                (9, Suit::Spades),
+                (5, Suit::Hearts),
+                (4, Suit::Clubs),
+                (3, Suit::Diamonds),
+                (2, Suit::Spades),
+            ])),
+            None
+        );
+    }
+
+    /// A hi-lo hand where seat `i` holds `holes[i]` and the board will be
+    /// 2♥ 3♦ 4♣ 9♠ K♦ unless `board` says otherwise.
+    fn hilo_hand(
+        variant: Variant,
+        holes: &[&str],
+        board: Option<&str>,
+        blinds: (u64, u64),
+    ) -> PokerGameState {
+        let cards = |s: &str| -> Vec<Card> {
+            s.split_whitespace()
+                .map(|c| Card::from_string(c).unwrap())
+                .collect()
+        };
+        let seats: Vec<(&str, u64)> = ["Alice", "Bob", "Carol"][..holes.len()]
+            .iter()
+            .map(|&name| (name, INITIAL_CHIPS))
+            .collect();
+        let mut state = GameBuilder::new()
+            .players(&seats)
+            .blinds(blinds.0, blinds.1)
+            .build();
+        state.variant = variant;
+        state.hi_lo = true;
+        state.start_new_hand().unwrap();
+
+        let board = cards(board.unwrap_or("2♥ 3♦ 4♣ 9♠ K♦"));
+        for (player, hole) in state.players.iter_mut().zip(holes) {
+            player.hole_cards = cards(hole);
         }
+        let placed: Vec<Card> = state
+            .players
+            .iter()
+            .flat_map(|p| p.hole_cards.clone())
+            .chain(board.iter().copied())
+            .collect();
+        state.deck.cards.retain(|c| !placed.contains(c));
+        state.deck.cards.splice(0..0, board);
+        state
+    }
 
-        if winners.is_empty() {
-            self.end_hand();
-            return;
+    fn check_down(state: &mut PokerGameState) {
+        while state.stage != GameStage::HandComplete {
+            let action = if state.can_check() {
+                PlayerAction::Check
+            } else {
+                PlayerAction::Call
+            };
+            state.perform_action(action).unwrap();
         }
+    }
 
-        let split_amount = self.pot / winners.len() as u64;
-        let remainder = self.pot % winners.len() as u64;
+    #[test]
+    fn test_hilo_showdown_scoop_pays_once() {
+        let mut state = hilo_hand(
+            Variant::TexasHoldem,
+            &["A♠ 5♥", "K♠ K♥"],
+            None,
+            (SMALL_BLIND_CHIPS, BIG_BLIND_CHIPS),
+        );
+        check_down(&mut state);
+
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.winners, vec![0]);
+        assert_eq!(result.amounts, vec![2 * BIG_BLIND_CHIPS]);
+        assert_eq!(result.best.map(|h| h.rank), Some(HandRank::Straight));
+        let awards = state
+            .event_log
+            .iter()
+            .filter(|e| matches!(e, HandEvent::PotAwarded { .. }))
+            .count();
+        assert_eq!(awards, 1);
+    }
 
-        for &winner_idx in &winners {
-            self.players[winner_idx].collect_pot(split_amount);
-        }
+    #[test]
+    fn test_hilo_showdown_splits_high_and_low() {
+        // Bob folds his small blind, leaving an odd pot of 25 for the other two.
+        let mut state = hilo_hand(
+            Variant::TexasHoldem,
+            &["K♠ K♥", "Q♣ Q♦", "7♠ 8♥"],
+            None,
+            (5, 10),
+        );
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        check_down(&mut state);
+
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.winners, vec![0, 2]);
+        assert_eq!(result.amounts, vec![13, 12]);
+        assert_eq!(state.players[0].get_chips(), INITIAL_CHIPS + 3);
+        assert_eq!(state.players[2].get_chips(), INITIAL_CHIPS + 2);
+
+        let mut state = hilo_hand(
+            Variant::TexasHoldem,
+            &["K♠ K♥", "Q♣ Q♦", "7♠ 8♥"],
+            None,
+            (5, 10),
+        );
+        state.chip_denomination = 10;
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        check_down(&mut state);
+        assert_eq!(state.last_result.unwrap().amounts, vec![15, 10]);
+    }
 
-        if remainder > 0 {
-            self.players[winners[0]].collect_pot(remainder);
+    #[test]
+    fn test_omaha_low_uses_exactly_two_hole_cards() {
+        // Alice's lone ace would make A-2-3-4-5 with the board in Hold'em, but
+        // Omaha needs two low hole cards, so Bob's 7-6 low and straight scoop.
+        let mut state = hilo_hand(
+            Variant::Omaha,
+            &["A♠ K♥ Q♣ J♦", "6♠ 7♥ 10♠ 10♥"],
+            Some("2♥ 3♦ 4♣ 5♠ 8♦"),
+            (SMALL_BLIND_CHIPS, BIG_BLIND_CHIPS),
+        );
+        check_down(&mut state);
+
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.winners, vec![1]);
+        assert_eq!(result.amounts, vec![2 * BIG_BLIND_CHIPS]);
+    }
+
+    #[test]
+    fn test_split_pot_hilo_needs_complete_board() {
+        let mut state = hilo_hand(
+            Variant::TexasHoldem,
+            &["A♠ 5♥", "K♠ K♥"],
+            None,
+            (SMALL_BLIND_CHIPS, BIG_BLIND_CHIPS),
+        );
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        assert_eq!(state.stage, GameStage::Flop);
+        assert_eq!(state.split_pot_hilo(), Err("Hi-lo needs a complete board"));
+        assert_eq!(state.stage, GameStage::Flop);
+
+        for _ in 0..4 {
+            state.perform_action(PlayerAction::Check).unwrap();
         }
+        assert_eq!(state.stage, GameStage::River);
+        state.perform_action(PlayerAction::BetTo(40)).unwrap();
+        assert_eq!(state.split_pot_hilo(), Err("Betting is still open"));
+        assert_eq!(state.stage, GameStage::River);
+
+        state.return_uncalled_bet();
+        state.stage = GameStage::Showdown;
+        assert_eq!(state.split_pot_hilo(), Ok(()));
+        assert_eq!(state.last_result.unwrap().winners, vec![0]);
+    }
 
-        self.end_hand();
+    #[test]
+    fn test_nut_low_draw_with_ace_deuce() {
+        let board = [
+            Card::new(3, Suit::Hearts),
+            Card::new(6, Suit::Clubs),
+            Card::new(13, Suit::Diamonds),
+        ];
+        let ace_deuce = [Card::new(14, Suit::Spades), Card::new(2, Suit::Diamonds)];
+        let ace_deuce_turn = [board[0], board[1], board[2], Card::new(12, Suit::Hearts)];
+        assert!(PokerHandEvaluator::nut_low_draw(&ace_deuce, &board));
+        assert!(PokerHandEvaluator::nut_low_draw(
+            &ace_deuce,
+            &ace_deuce_turn
+        ));
+
+        let seven_eight = [Card::new(7, Suit::Spades), Card::new(8, Suit::Diamonds)];
+        assert!(!PokerHandEvaluator::nut_low_draw(&seven_eight, &board));
+
+        let made = [board[0], board[1], Card::new(5, Suit::Spades)];
+        assert!(!PokerHandEvaluator::nut_low_draw(&ace_deuce, &made));
     }
 
-    fn end_hand(&mut self) {
-        self.stage = GameStage::HandComplete;
-        self.dealer_position = (self.dealer_position + 1) % self.players.len();
+    #[test]
+    fn test_nut_high_draw_with_nut_flush_draw() {
+        let turn = [
+            Card::new(9, Suit::Hearts),
+            Card::new(6, Suit::Hearts),
+            Card::new(2, Suit::Clubs),
+            Card::new(3, Suit::Diamonds),
+        ];
+        let nut_flush_draw = [Card::new(14, Suit::Hearts), Card::new(13, Suit::Hearts)];
+        assert!(PokerHandEvaluator::nut_high_draw(&nut_flush_draw, &turn));
+        let weak = [Card::new(10, Suit::Clubs), Card::new(4, Suit::Clubs)];
+        assert!(!PokerHandEvaluator::nut_high_draw(&weak, &turn));
+
+        // A heart gives A♥3♥ the nut flush only because nobody else can hold
+        // the ace of hearts.
+        let turn = [
+            Card::new(5, Suit::Hearts),
+            Card::new(4, Suit::Hearts),
+            Card::new(2, Suit::Clubs),
+            Card::new(13, Suit::Diamonds),
+        ];
+        let ace_three = [Card::new(14, Suit::Hearts), Card::new(3, Suit::Hearts)];
+        assert!(PokerHandEvaluator::nut_high_draw(&ace_three, &turn));
     }
 
-    #[must_use]
-    pub fn get_stage_string(&self) -> &'static str {
-        match self.stage {
-            GameStage::Preflop => "Preflop",
-            GameStage::Flop => "Flop",
-            GameStage::Turn => "Turn",
-            GameStage::River => "River",
-            GameStage::Showdown => "Showdown",
-            GameStage::HandComplete => "Complete",
-            GameStage::WaitingToStart => "Waiting",
+    #[test]
+    fn test_burn_per_street_changes_deck_depletion() {
+        let play_to_river = |burns: usize| {
+            let mut state = PokerGameState::new_seeded(9);
+            state.burn_per_street = burns;
+            state.start_new_hand().unwrap();
+            state.perform_action(PlayerAction::Call).unwrap();
+            state.perform_action(PlayerAction::Check).unwrap();
+            for _ in 0..2 {
+                state.perform_action(PlayerAction::Check).unwrap();
+                state.perform_action(PlayerAction::Check).unwrap();
+            }
+            assert_eq!(state.stage, GameStage::River);
+            state
+        };
+
+        let standard = play_to_river(1);
+        let no_burns = play_to_river(0);
+        let double = play_to_river(2);
+        assert_eq!(no_burns.deck.cards.len(), standard.deck.cards.len() + 3);
+        assert_eq!(double.deck.cards.len() + 3, standard.deck.cards.len());
+        assert_eq!(
+            no_burns
+                .dealing_plan()
+                .iter()
+                .filter(|&&step| step == DealStep::Burn)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_straight_flush_outs_open_ended() {
+        let hole = [Card::new(9, Suit::Hearts), Card::new(8, Suit::Hearts)];
+        let flop = [
+            Card::new(7, Suit::Hearts),
+            Card::new(6, Suit::Hearts),
+            Card::new(13, Suit::Clubs),
+        ];
+
+        assert_eq!(
+            PokerHandEvaluator::straight_flush_outs(&hole, &flop),
+            vec![Card::new(5, Suit::Hearts), Card::new(10, Suit::Hearts)]
+        );
+        let offsuit = [Card::new(9, Suit::Spades), Card::new(8, Suit::Hearts)];
+        assert!(PokerHandEvaluator::straight_flush_outs(&offsuit, &flop).is_empty());
+
+        // With the straight flush made, only the ten improves it.
+        let made = [
+            Card::new(7, Suit::Hearts),
+            Card::new(6, Suit::Hearts),
+            Card::new(5, Suit::Hearts),
+        ];
+        assert_eq!(
+            PokerHandEvaluator::straight_flush_outs(&hole, &made),
+            vec![Card::new(10, Suit::Hearts)]
+        );
+    }
+
+    #[test]
+    fn test_acting_after_hand_complete_is_wrong_stage() {
+        let mut state = PokerGameState::new();
+        assert_eq!(
+            state.perform_action(PlayerAction::Check),
+            Err("Wrong stage")
+        );
+
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        assert_eq!(state.stage, GameStage::HandComplete);
+        let chips: Vec<u64> = state.players.iter().map(Player::get_chips).collect();
+
+        for action in [PlayerAction::Check, PlayerAction::Call, PlayerAction::AllIn] {
+            assert!(!state.is_action_legal(action));
+            assert_eq!(state.perform_action(action), Err("Wrong stage"));
         }
+        let after: Vec<u64> = state.players.iter().map(Player::get_chips).collect();
+        assert_eq!(after, chips);
     }
 
-    pub fn set_bet_amount(&mut self, amount: f32) {
-        let amount = amount as u64;
-        if amount >= self.min_bet && amount <= self.max_bet {
-            self.bet_amount = amount;
+    #[test]
+    fn test_chip_history_has_entry_per_hand() {
+        let mut state = PokerGameState::new();
+        for _ in 0..3 {
+            state.start_new_hand().unwrap();
+            state.perform_action(PlayerAction::Fold).unwrap();
+        }
+
+        for player in &state.players {
+            assert_eq!(player.get_chip_history().len(), 3);
+            assert_eq!(player.get_chip_history().last(), Some(&player.get_chips()));
         }
+        assert_ne!(
+            state.players[0].get_chip_history()[0],
+            state.players[0].get_chip_history()[1]
+        );
     }
 
-    #[must_use]
-    pub fn is_pending_action(&self) -> bool {
-        self.pending_action
+    #[test]
+    fn test_public_snapshot_omits_deck_and_opponent_cards() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+
+        let snapshot = state.public_snapshot();
+        let hero = state.current_player;
+        let villain = 1 - hero;
+        assert_eq!(snapshot.hero, hero);
+        assert_eq!(snapshot.hero_cards, state.players[hero].get_hole_cards());
+        assert_eq!(snapshot.community_cards, state.community_cards);
+        assert_eq!(snapshot.pot, state.pot);
+        assert_eq!(
+            snapshot.players[villain].chips,
+            state.players[villain].get_chips()
+        );
+
+        let visible: Vec<Card> = snapshot
+            .hero_cards
+            .iter()
+            .chain(&snapshot.community_cards)
+            .copied()
+            .collect();
+        assert_eq!(visible.len(), 5);
+        assert!(state.deck.cards.iter().all(|c| !visible.contains(c)));
+        assert!(state.players[villain]
+            .get_hole_cards()
+            .iter()
+            .all(|c| !visible.contains(c)));
     }
 
-    #[must_use]
-    pub fn get_current_player(&self) -> usize {
-        self.current_player
+    #[test]
+    fn test_bet_fraction_and_bb_multiples() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.bet_bb(3.0), 3 * BIG_BLIND_CHIPS);
+        assert_eq!(state.bet_fraction(1.0), state.min_bet);
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+
+        assert_eq!(state.pot, 40);
+        assert_eq!(state.bet_fraction(1.0), 40);
+        assert_eq!(state.bet_fraction(0.5), BIG_BLIND_CHIPS);
+        assert_eq!(state.bet_fraction(0.25), state.min_bet);
+
+        let current = state.current_player;
+        state.players[current].chips = 30;
+        let max_bet = state.set_bet_amount(u64::MAX);
+        assert_eq!(max_bet, 30);
+        assert_eq!(state.bet_fraction(1.0), max_bet);
+        assert_eq!(state.bet_bb(10.0), max_bet);
     }
 
-    #[must_use]
-    pub fn can_check(&self) -> bool {
-        self.players
-            .get(self.current_player)
-            .is_some_and(|p| !p.is_folded() && p.get_current_bet() == self.to_call)
+    #[test]
+    fn test_chip_denomination_avoids_slivers() {
+        let mut state = PokerGameState::new();
+        state
+            .players
+            .push(Player::new("Carol".to_string(), INITIAL_CHIPS));
+        assert_eq!(state.shares(3, 50), vec![18, 16, 16]);
+
+        state.chip_denomination = 5;
+        assert_eq!(state.shares(3, 50), vec![20, 15, 15]);
+
+        state.start_new_hand().unwrap();
+        assert_eq!(state.set_bet_amount(63), 60);
+        assert_eq!(state.bet_bb(2.7), 50);
     }
 
-    #[must_use]
-    pub fn can_call(&self) -> bool {
-        self.players.get(self.current_player).is_some_and(|p| {
-            !p.is_folded() && p.get_chips() > 0 && p.get_current_bet() < self.to_call
-        })
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_force_community_sets_next_flop() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let flop = [
+            state.deck.cards[20],
+            state.deck.cards[10],
+            state.deck.cards[30],
+        ];
+        let held = state.players[0].get_hole_cards()[0];
+        assert_eq!(state.force_community(&[held]), Err("Card already dealt"));
+        assert_eq!(
+            state.force_community(&[flop[0], flop[0]]),
+            Err("Card already dealt")
+        );
+
+        let deck_size = state.deck.len();
+        state.force_community(&flop).unwrap();
+        assert_eq!(state.deck.len(), deck_size);
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+
+        assert_eq!(state.flop(), &flop);
+        assert!(flop.iter().all(|c| !state.deck.cards.contains(c)));
     }
 
-    #[must_use]
-    pub fn can_bet(&self) -> bool {
-        self.players
-            .get(self.current_player)
-            .is_some_and(|p| !p.is_folded() && p.get_chips() > 0 && self.to_call == 0)
+    #[test]
+    fn test_session_save_load_round_trip() {
+        let mut original = PokerGameState::new_seeded(11);
+        for _ in 0..2 {
+            original.start_new_hand().unwrap();
+            original.perform_action(PlayerAction::Fold).unwrap();
+        }
+        original.small_blind = 25;
+        original.big_blind = 50;
+
+        let session = Session::capture(&original).unwrap();
+        let path = std::env::temp_dir().join(format!("poker-session-{}.json", std::process::id()));
+        session.save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, session);
+
+        let mut resumed = loaded.into_state();
+        for _ in 0..3 {
+            original.start_new_hand().unwrap();
+            resumed.start_new_hand().unwrap();
+            for (a, b) in original.players.iter().zip(&resumed.players) {
+                assert_eq!(a.get_hole_cards(), b.get_hole_cards());
+                assert_eq!(a.get_chips(), b.get_chips());
+            }
+            assert_eq!(original.dealer_position, resumed.dealer_position);
+            original.perform_action(PlayerAction::Fold).unwrap();
+            resumed.perform_action(PlayerAction::Fold).unwrap();
+        }
+        assert!(Session::load(&path).is_err());
     }
 
-    #[must_use]
-    pub fn can_raise(&self) -> bool {
-        self.players.get(self.current_player).is_some_and(|p| {
-            !p.is_folded()
-                && p.get_chips() > 0
-                && self.to_call > 0
-                && p.get_current_bet() < self.to_call + self.min_bet
-        })
+    #[test]
+    fn test_pre_deal_hook_gives_player_zero_aces() {
+        let aces = [Card::new(14, Suit::Spades), Card::new(14, Suit::Hearts)];
+        let mut state = PokerGameState::new();
+        state.pre_deal_hook = Some(Rc::new(RefCell::new(move |deck: &mut Deck| {
+            deck.cards.retain(|card| !aces.contains(card));
+            deck.cards.splice(0..0, aces);
+        })));
+
+        for _ in 0..3 {
+            state.start_new_hand().unwrap();
+            assert_eq!(state.players[0].get_hole_cards(), &aces);
+            assert!(state.players[1]
+                .get_hole_cards()
+                .iter()
+                .all(|c| !aces.contains(c)));
+            state.perform_action(PlayerAction::Fold).unwrap();
+        }
     }
 
-    #[must_use]
-    pub fn can_fold(&self) -> bool {
-        self.players
-            .get(self.current_player)
-            .is_some_and(|p| !p.is_folded())
+    #[test]
+    fn test_outstanding_bets_mid_street() {
+        let mut state = PokerGameState::new();
+        state
+            .players
+            .push(Player::new("Carol".to_string(), INITIAL_CHIPS));
+        state.start_new_hand().unwrap();
+        assert_eq!(
+            state.outstanding_bets(),
+            vec![(0, BIG_BLIND_CHIPS), (1, SMALL_BLIND_CHIPS), (2, 0)]
+        );
+
+        state.perform_action(PlayerAction::RaiseTo(60)).unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        assert_eq!(state.outstanding_bets(), vec![(0, 0), (2, 40)]);
+        assert_eq!(state.get_call_amount(), 40);
     }
 
-    #[must_use]
-    pub fn get_call_amount(&self) -> u64 {
-        self.players
-            .get(self.current_player)
-            .map_or(0, |p| self.to_call.saturating_sub(p.get_current_bet()))
+    #[test]
+    fn test_exhausted_deck_errors_instead_of_short_board() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.deck.cards.truncate(3);
+        state.perform_action(PlayerAction::Call).unwrap();
+
+        assert_eq!(
+            state.perform_action(PlayerAction::Check),
+            Err("Deck exhausted")
+        );
+        assert!(state.community_cards.is_empty());
+        assert_eq!(state.deck.len(), 3);
+        assert_eq!(state.stage, GameStage::Preflop);
+
+        state.players[0].chips = 0;
+        state.players[1].chips = 0;
+        state.players[0].all_in = true;
+        state.players[1].all_in = true;
+        assert_eq!(state.advance_if_no_action(), Err("Deck exhausted"));
+        state.run_twice_on_all_in = true;
+        assert_eq!(state.advance_if_no_action(), Err("Deck exhausted"));
+        assert!(state.community_cards.is_empty());
     }
 
-    pub fn update_pot_odds(&mut self) {
-        let call_amount = self.get_call_amount();
-        let total_pot = self.pot.saturating_add(call_amount);
-        self.pot_odds = if call_amount > 0 && total_pot > 0 {
-            call_amount as f32 / total_pot as f32
-        } else {
-            0.0
-        };
+    #[test]
+    fn test_new_with_chips_sets_starting_stacks() {
+        let mut state = PokerGameState::new_with_chips(2500);
+        assert!(state.players.iter().all(|p| p.get_chips() == 2500));
+
+        state.start_new_hand().unwrap();
+        assert_eq!(
+            state.players[0].get_chips() + state.players[1].get_chips() + state.pot,
+            5000
+        );
+        assert_eq!(state.max_raise_to(), 2500);
     }
-}
 
-impl Default for PokerGameState {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_set_blinds_only_between_hands() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert_eq!(
+            state.set_blinds(50, 100),
+            Err("Cannot change blinds while a hand is in progress")
+        );
+        state.perform_action(PlayerAction::Fold).unwrap();
+
+        assert_eq!(
+            state.set_blinds(100, 50),
+            Err("Small blind cannot exceed big blind")
+        );
+        state.set_blinds(50, 100).unwrap();
+        state.start_new_hand().unwrap();
+        let posted: Vec<u64> = state.players.iter().map(Player::get_current_bet).collect();
+        assert!(posted.contains(&50) && posted.contains(&100));
+        assert_eq!(state.to_call, 100);
+        assert_eq!(state.pot, 150);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn test_deck_creation() {
-        let deck = Deck::new();
-        assert_eq!(deck.len(), 52);
+    fn test_riffle_is_less_random_than_fisher_yates() {
+        let ordered = Deck::new().cards;
+        let kept_neighbours = |deck: &Deck| {
+            deck.cards
+                .windows(2)
+                .filter(|w| {
+                    let i = ordered.iter().position(|c| *c == w[0]).unwrap();
+                    ordered.get(i + 1) == Some(&w[1])
+                })
+                .count()
+        };
+        let mut rng = StdRng::seed_from_u64(5);
+        let trials = 2600;
+        let mut top_card_positions = [0u32; 52];
+        let (mut fisher_yates_kept, mut riffle_kept) = (0, 0);
+        for _ in 0..trials {
+            let mut deck = Deck::new();
+            deck.shuffle_with(ShuffleMethod::FisherYates, &mut rng);
+            fisher_yates_kept += kept_neighbours(&deck);
+            top_card_positions[deck.cards.iter().position(|c| *c == ordered[0]).unwrap()] += 1;
+
+            let mut deck = Deck::new();
+            deck.shuffle_with(ShuffleMethod::Riffle { passes: 1 }, &mut rng);
+            assert!(deck.is_standard_complete());
+            riffle_kept += kept_neighbours(&deck);
+        }
+
+        assert!(top_card_positions.iter().all(|&n| (20..=80).contains(&n)));
+        assert!(fisher_yates_kept < 2 * trials);
+        assert!(riffle_kept > 20 * trials);
     }
 
     #[test]
-    fn test_deck_shuffle() {
-        let mut deck1 = Deck::new();
-        let mut deck2 = Deck::new();
-        deck1.shuffle();
-        deck2.shuffle();
+    fn test_all_cards_are_unique_and_match_new_deck() {
+        let all = Deck::all_cards();
+        let unique: std::collections::HashSet<Card> = all.iter().copied().collect();
+        assert_eq!(unique.len(), 52);
+        assert_eq!(all[0], Card::new(2, Suit::Spades));
+        assert_eq!(all[51], Card::new(14, Suit::Clubs));
+        assert_eq!(Deck::new().cards, all.to_vec());
+    }
 
-        let cards1: Vec<String> = deck1.cards.iter().map(|c| c.to_string()).collect();
-        let cards2: Vec<String> = deck2.cards.iter().map(|c| c.to_string()).collect();
+    #[test]
+    fn test_card_gap() {
+        let gap = |a: &str, b: &str| {
+            card_gap(Card::from_string(a).unwrap(), Card::from_string(b).unwrap())
+        };
+        assert_eq!(gap("A♠", "K♥"), 1);
+        assert_eq!(gap("A♠", "2♥"), 1);
+        assert_eq!(gap("5♦", "9♣"), 4);
+        assert_eq!(gap("9♣", "5♦"), 4);
+        assert_eq!(gap("7♠", "7♥"), 0);
+        assert_eq!(gap("A♠", "A♥"), 0);
+    }
 
-        assert_ne!(cards1, cards2);
+    #[test]
+    fn test_classify_hole_and_label() {
+        let card = |s: &str| Card::from_string(s).unwrap();
+        assert_eq!(classify_hole(card("10♠"), card("10♥")), HoleClass::Pair);
+        assert_eq!(classify_hole(card("A♠"), card("K♠")), HoleClass::Suited);
+        assert_eq!(classify_hole(card("7♦"), card("2♣")), HoleClass::Offsuit);
+
+        assert_eq!(hole_label(card("10♠"), card("10♥")), "TT");
+        assert_eq!(hole_label(card("K♠"), card("A♠")), "AKs");
+        assert_eq!(hole_label(card("2♣"), card("7♦")), "72o");
+        assert_eq!(hole_label(card("9♥"), card("J♥")), "J9s");
     }
 
     #[test]
-    fn test_deck_deal() {
-        let mut deck = Deck::new();
-        let cards = deck.deal(5).unwrap();
-        assert_eq!(cards.len(), 5);
-        assert_eq!(deck.len(), 47);
+    fn test_parse_range() {
+        let pairs: HandRange = (10..=14).map(|r| (r, r, HoleClass::Pair)).collect();
+        assert_eq!(parse_range("TT+"), pairs);
+
+        let suited_aces: HandRange = (11..=13).map(|r| (14, r, HoleClass::Suited)).collect();
+        assert_eq!(parse_range("AJs+"), suited_aces);
+
+        let range = parse_range("99+, AQs+, KJo, AK, bogus");
+        assert_eq!(range.len(), 6 + 2 + 1 + 1);
+        assert!(range.contains(&(13, 11, HoleClass::Offsuit)));
+        assert!(range.contains(&(14, 13, HoleClass::Offsuit)));
+        assert!(!range.contains(&(13, 11, HoleClass::Suited)));
     }
 
     #[test]
-    fn test_deck_deal_insufficient() {
-        let mut deck = Deck::new();
-        let _ = deck.deal(50).unwrap();
-        assert!(deck.deal(5).is_none());
+    fn test_range_contains() {
+        let card = |s: &str| Card::from_string(s).unwrap();
+        let range = parse_range("KJs+");
+        assert!(range_contains(&range, card("K♥"), card("J♥")));
+        assert!(range_contains(&range, card("Q♥"), card("K♥")));
+        assert!(!range_contains(&range, card("K♥"), card("9♥")));
+        assert!(!range_contains(&range, card("K♥"), card("J♠")));
     }
 
     #[test]
-    fn test_card_to_string() {
-        let ace_spades = Card::new(14, Suit::Spades);
-        assert_eq!(ace_spades.to_string(), "A♠");
+    fn test_equity_vs_range() {
+        let card = |s: &str| Card::from_string(s).unwrap();
+        let aces = [card("A♥"), card("A♠")];
 
-        let ten_hearts = Card::new(10, Suit::Hearts);
-        assert_eq!(ten_hearts.to_string(), "10♥");
+        let wide = parse_range("22+, A2+, K2+, Q2+, J2+, T2+, 92+, 82+");
+        assert!(equity_vs_range(&aces, &wide, &[], 3000) > 0.75);
 
-        let two_clubs = Card::new(2, Suit::Clubs);
-        assert_eq!(two_clubs.to_string(), "2♣");
+        // Only the other two aces are live, so it is close to a split.
+        let other_aces = equity_vs_range(&aces, &parse_range("AA"), &[], 2000);
+        assert!((0.4..0.6).contains(&other_aces));
+
+        assert_eq!(equity_vs_range(&aces, &HandRange::new(), &[], 100), 0.0);
+        assert_eq!(equity_vs_range(&aces[..1], &wide, &[], 100), 0.0);
     }
 
     #[test]
-    fn test_card_from_string() {
-        assert_eq!(Card::from_string("A♠"), Some(Card::new(14, Suit::Spades)));
-        assert_eq!(Card::from_string("10♥"), Some(Card::new(10, Suit::Hearts)));
-        assert_eq!(Card::from_string("2♣"), Some(Card::new(2, Suit::Clubs)));
-        assert_eq!(Card::from_string("invalid"), None);
+    fn test_validate_catches_broken_invariants() {
+        let mut state = PokerGameState::new_seeded(4);
+        state.start_new_hand().unwrap();
+        assert_eq!(state.validate(), Ok(()));
+
+        let mut duplicate = state.clone();
+        let card = duplicate.players[0].hole_cards[0];
+        duplicate.community_cards.push(card);
+        assert!(duplicate.validate().unwrap_err().contains("Duplicate card"));
+
+        let mut short_pot = state.clone();
+        short_pot.pot -= 1;
+        assert!(short_pot.validate().unwrap_err().contains("committed"));
+
+        let mut folded = state.clone();
+        let current = folded.current_player;
+        folded.players[current].folded = true;
+        assert!(folded.validate().unwrap_err().contains("not in the hand"));
     }
 
     #[test]
-    fn test_card_is_red() {
-        assert!(Card::new(5, Suit::Hearts).is_red());
-        assert!(Card::new(5, Suit::Diamonds).is_red());
-        assert!(!Card::new(5, Suit::Spades).is_red());
-        assert!(!Card::new(5, Suit::Clubs).is_red());
+    fn test_history_by_street_groups_actions() {
+        let mut state = PokerGameState::new_seeded(8);
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        state.perform_action(PlayerAction::BetTo(40)).unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+
+        let history = state.history_by_street();
+        let stages: Vec<GameStage> = history.iter().map(|(stage, _)| *stage).collect();
+        assert_eq!(stages, vec![GameStage::Preflop, GameStage::Flop]);
+
+        let actions = |events: &[HandEvent]| -> Vec<PlayerAction> {
+            events
+                .iter()
+                .filter_map(|e| match e {
+                    HandEvent::Action { action, .. } => Some(*action),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(
+            actions(&history[0].1),
+            vec![PlayerAction::Call, PlayerAction::Check]
+        );
+        assert_eq!(
+            actions(&history[1].1),
+            vec![PlayerAction::BetTo(40), PlayerAction::Fold]
+        );
+        assert!(matches!(history[0].1[0], HandEvent::HandStarted { .. }));
+        assert!(matches!(history[1].1[0], HandEvent::StreetDealt { .. }));
+        assert!(matches!(
+            history[1].1.last(),
+            Some(HandEvent::PotAwarded { .. })
+        ));
     }
 
     #[test]
-    fn test_player_betting() {
-        let mut player = Player::new("Test".to_string(), 1000);
-        assert_eq!(player.get_chips(), 1000);
+    fn test_compute_showdown_is_pure() {
+        let mut state = PokerGameState::new_seeded(21);
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        for _ in 0..2 {
+            state.perform_action(PlayerAction::Check).unwrap();
+            state.perform_action(PlayerAction::Check).unwrap();
+        }
+        state.perform_action(PlayerAction::BetTo(100)).unwrap();
+        assert_eq!(state.stage, GameStage::River);
+
+        let stacks: Vec<u64> = state.players.iter().map(Player::get_chips).collect();
+        let (pot, log_len) = (state.pot, state.event_log.len());
+        let first = state.compute_showdown();
+        let second = state.compute_showdown();
+        assert_eq!(first, second);
+        assert_eq!(first.reason, WinReason::Showdown);
+        assert_eq!(first.amounts.iter().sum::<u64>(), 2 * BIG_BLIND_CHIPS);
+        let after: Vec<u64> = state.players.iter().map(Player::get_chips).collect();
+        assert_eq!(after, stacks);
+        assert_eq!((state.pot, state.event_log.len()), (pot, log_len));
+
+        state.perform_action(PlayerAction::Call).unwrap();
+        let settled = state.last_result.clone().unwrap();
+        assert_eq!(settled.winners, first.winners);
+        assert_eq!(settled.best, first.best);
+    }
 
-        let result = player.bet(500);
-        assert!(result.is_ok());
-        assert_eq!(player.get_chips(), 500);
-        assert_eq!(player.get_current_bet(), 500);
-        assert!(!player.is_all_in());
+    #[test]
+    fn test_hand_key_is_suit_isomorphic() {
+        let cards = |s: &str| -> Vec<Card> {
+            s.split_whitespace()
+                .map(|c| Card::from_string(c).unwrap())
+                .collect()
+        };
+        let key = |hole: &str, board: &str| hand_key(&cards(hole), &cards(board));
 
-        let result = player.bet(500);
-        assert!(result.is_ok());
-        assert_eq!(player.get_chips(), 0);
-        assert!(player.is_all_in());
+        assert_eq!(key("A♥ K♥", ""), key("A♠ K♠", ""));
+        assert_eq!(key("A♥ K♠", ""), key("K♣ A♦", ""));
+        assert_ne!(key("A♥ K♥", ""), key("A♥ K♠", ""));
+        assert_ne!(key("A♥ K♥", ""), key("A♥ Q♥", ""));
 
-        let result = player.bet(100);
-        assert!(result.is_err());
+        assert_eq!(key("A♥ K♥", "2♥ 7♠ 9♠"), key("A♦ K♦", "9♣ 2♦ 7♣"));
+        assert_ne!(key("A♥ K♥", "2♥ 7♠ 9♠"), key("A♥ K♥", "2♠ 7♠ 9♠"));
+
+        let preflop: HashSet<u64> = all_starting_hands()
+            .map(|(a, b)| hand_key(&[a, b], &[]))
+            .collect();
+        assert_eq!(preflop.len(), 169);
+        assert_eq!(key("2♣ 3♣", "4♣"), (3 << 24) | (2 << 6) | 1);
     }
 
     #[test]
-    fn test_hand_evaluator_high_card() {
-        let hole_cards = vec![Card::new(2, Suit::Spades), Card::new(5, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(8, Suit::Diamonds),
-            Card::new(10, Suit::Clubs),
-            Card::new(12, Suit::Spades),
-        ];
+    fn test_starting_hand_counts() {
+        assert_eq!(all_starting_hands().count(), 1326);
+        let combos: HashSet<u64> = all_starting_hands()
+            .map(|(a, b)| cards_to_mask(&[a, b]))
+            .collect();
+        assert_eq!(combos.len(), 1326);
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::HighCard);
+        let classes: HandRange = canonical_starting_hands().collect();
+        assert_eq!(canonical_starting_hands().count(), 169);
+        assert_eq!(classes.len(), 169);
+        let from_combos: HandRange = all_starting_hands().map(|(a, b)| hole_key(a, b)).collect();
+        assert_eq!(from_combos, classes);
     }
 
     #[test]
-    fn test_hand_evaluator_pair() {
-        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(10, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(5, Suit::Diamonds),
-            Card::new(8, Suit::Clubs),
-            Card::new(12, Suit::Spades),
-        ];
+    fn test_implied_odds_lower_required_equity() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.pot = 300;
+        state.to_call = state.players[state.current_player].get_current_bet() + 100;
+
+        let raw = state.pot_odds_detail().breakeven_equity;
+        assert!((state.implied_odds(0) - raw).abs() < f64::EPSILON);
+        assert!((state.implied_odds(600) - 0.1).abs() < f64::EPSILON);
+        assert!(state.implied_odds(600) < raw);
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::Pair);
+    #[test]
+    fn test_narrator_describes_actions() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        let raise = HandEvent::Action {
+            player: 0,
+            stage: GameStage::Preflop,
+            action: PlayerAction::RaiseTo(200),
+            amount: 190,
+            total_bet: 200,
+        };
+        assert_eq!(state.narrate(&raise), "Alice raises to 200");
+
+        let message = state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(
+            message,
+            format!("Bob calls {}", BIG_BLIND_CHIPS - SMALL_BLIND_CHIPS)
+        );
+
+        struct Terse;
+        impl Narrator for Terse {
+            fn narrate(&self, event: &HandEvent, _players: &[Player]) -> String {
+                match event {
+                    HandEvent::Action { action, .. } => format!("{action:?}"),
+                    _ => String::new(),
+                }
+            }
+        }
+        state.set_narrator(Rc::new(Terse));
+        assert_eq!(state.perform_action(PlayerAction::Check).unwrap(), "Check");
     }
 
     #[test]
-    fn test_hand_evaluator_flush() {
-        let hole_cards = vec![Card::new(2, Suit::Hearts), Card::new(7, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(5, Suit::Hearts),
-            Card::new(10, Suit::Hearts),
-            Card::new(12, Suit::Hearts),
-        ];
+    fn test_short_all_in_wins_only_the_main_pot() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000), ("Carol", 100)])
+            .build();
+        state.start_new_hand().unwrap();
+        let card = |s: &str| Card::from_string(s).unwrap();
+        state.players[0].hole_cards = vec![card("7♣"), card("2♦")];
+        state.players[1].hole_cards = vec![card("8♣"), card("3♦")];
+        state.players[2].hole_cards = vec![card("A♠"), card("A♥")];
+        let board = ["3♠", "9♥", "J♦", "K♣", "5♠"].map(card);
+        let placed: Vec<Card> = state
+            .players
+            .iter()
+            .flat_map(|p| p.hole_cards.clone())
+            .chain(board)
+            .collect();
+        state.deck.cards.retain(|c| !placed.contains(c));
+        state.deck.cards.splice(0..0, board);
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::Flush);
+        while state.stage != GameStage::HandComplete {
+            state.perform_action(PlayerAction::AllIn).unwrap();
+        }
+
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.winners, vec![2, 1]);
+        assert_eq!(result.amounts, vec![300, 1800]);
+        let chips: Vec<u64> = state.players.iter().map(Player::get_chips).collect();
+        assert_eq!(chips, vec![0, 1800, 300]);
     }
 
     #[test]
-    fn test_hand_evaluator_straight() {
-        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(8, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(6, Suit::Diamonds),
-            Card::new(7, Suit::Clubs),
-            Card::new(9, Suit::Spades),
-        ];
+    fn test_run_it_twice_respects_side_pots() {
+        for seed in 0..20 {
+            let mut state = GameBuilder::new()
+                .players(&[("Alice", 1000), ("Bob", 1000), ("Carol", 100)])
+                .deck({
+                    let mut deck = Deck::new();
+                    deck.shuffle_with_rng(&mut StdRng::seed_from_u64(seed));
+                    deck
+                })
+                .build();
+            state.run_twice_on_all_in = true;
+            state.start_new_hand().unwrap();
+            while state.stage != GameStage::HandComplete {
+                state.perform_action(PlayerAction::AllIn).unwrap();
+            }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::Straight);
+            let result = state.last_result.clone().unwrap();
+            assert_eq!(result.amounts.iter().sum::<u64>(), 2100);
+            let carol = result
+                .winners
+                .iter()
+                .zip(&result.amounts)
+                .find(|&(&w, _)| w == 2)
+                .map_or(0, |(_, &amount)| amount);
+            assert!(carol <= 300, "seed {seed}: Carol won {carol}");
+            assert_eq!(state.players[2].get_chips(), carol);
+        }
     }
 
     #[test]
-    fn test_hand_evaluator_full_house() {
-        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(5, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(5, Suit::Diamonds),
-            Card::new(10, Suit::Clubs),
-            Card::new(10, Suit::Spades),
-        ];
+    fn test_raise_to_below_minimum_is_rejected() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000)])
+            .blinds(10, 20)
+            .build();
+        state.start_new_hand().unwrap();
+        assert_eq!(
+            state.perform_action(PlayerAction::RaiseTo(21)),
+            Err("Raise below minimum")
+        );
+        assert_eq!(
+            state.perform_action(PlayerAction::RaiseTo(1001)),
+            Err("Insufficient chips")
+        );
+        state.perform_action(PlayerAction::RaiseTo(40)).unwrap();
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::FullHouse);
+    #[test]
+    fn test_bet_to_below_minimum_is_rejected() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000)])
+            .blinds(10, 20)
+            .build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        assert_eq!(state.stage, GameStage::Flop);
+
+        assert_eq!(
+            state.perform_action(PlayerAction::BetTo(1)),
+            Err("Bet below minimum")
+        );
+        assert_eq!(
+            state.perform_action(PlayerAction::BetTo(0)),
+            Err("Bet below minimum")
+        );
+        assert_eq!(
+            state.perform_action(PlayerAction::BetTo(981)),
+            Err("Insufficient chips")
+        );
+        state.perform_action(PlayerAction::BetTo(20)).unwrap();
     }
 
     #[test]
-    fn test_hand_evaluator_four_of_a_kind() {
-        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(5, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(5, Suit::Diamonds),
-            Card::new(5, Suit::Clubs),
-            Card::new(10, Suit::Spades),
-        ];
+    fn test_short_all_in_target_is_allowed() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 30)])
+            .blinds(10, 20)
+            .build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        let bob = state.current_player;
+        assert_eq!(state.players[bob].get_name(), "Bob");
+        assert_eq!(
+            state.perform_action(PlayerAction::BetTo(5)),
+            Err("Bet below minimum")
+        );
+        state.perform_action(PlayerAction::BetTo(10)).unwrap();
+        assert!(state.players[bob].is_all_in());
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::FourOfAKind);
+    #[test]
+    fn test_play_continues_after_set_board() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000)])
+            .build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        state.perform_action(PlayerAction::Check).unwrap();
+        assert_eq!(state.stage, GameStage::Flop);
+
+        let old_flop = state.flop().to_vec();
+        let board: Vec<Card> = state.deck.cards[..3].to_vec();
+        state.set_board(board.clone()).unwrap();
+        assert!(board.iter().all(|card| !state.deck.cards.contains(card)));
+        assert!(old_flop.iter().all(|card| state.deck.cards.contains(card)));
+
+        check_down(&mut state);
+        assert_eq!(state.flop(), &board[..]);
+        assert_eq!(state.board_cards().len(), 5);
+        assert_eq!(state.validate(), Ok(()));
     }
 
     #[test]
-    fn test_hand_evaluator_straight_flush() {
-        let hole_cards = vec![Card::new(6, Suit::Hearts), Card::new(7, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(8, Suit::Hearts),
-            Card::new(9, Suit::Hearts),
-            Card::new(10, Suit::Hearts),
-        ];
+    fn test_all_in_call_is_vpip_and_two_runs_are_one_win() {
+        let mut double_win = false;
+        for seed in 0..50 {
+            let mut state = PokerGameState::new_seeded(seed);
+            state.run_twice_on_all_in = true;
+            state.start_new_hand().unwrap();
+            state.perform_action(PlayerAction::AllIn).unwrap();
+            state.perform_action(PlayerAction::AllIn).unwrap();
+            assert!(state.players.iter().all(|p| p.get_stats().vpip_hands == 1));
+
+            let awards = |seat: usize| {
+                state
+                    .event_log
+                    .iter()
+                    .filter(
+                        |e| matches!(e, HandEvent::PotAwarded { player, .. } if *player == seat),
+                    )
+                    .count()
+            };
+            if let Some(seat) = (0..2).find(|&seat| awards(seat) == 2) {
+                let stats = state.players[seat].get_stats();
+                assert_eq!(stats.hands_won, 1);
+                assert_eq!(stats.chips_won, 2 * INITIAL_CHIPS);
+                double_win = true;
+                break;
+            }
+        }
+        assert!(double_win);
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::StraightFlush);
+    #[test]
+    fn test_checked_through_river_reveals_from_hand_button() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000), ("Carol", 1000)])
+            .dealer(0)
+            .build();
+        state.start_new_hand().unwrap();
+        check_down(&mut state);
+        assert_eq!(state.stage, GameStage::HandComplete);
+        assert_eq!(state.last_aggressor, None);
+        assert_eq!(state.dealer_position, 1);
+        assert_eq!(state.showdown_reveal_order(), vec![1, 2, 0]);
     }
 
     #[test]
-    fn test_hand_evaluator_royal_flush() {
-        let hole_cards = vec![Card::new(10, Suit::Hearts), Card::new(14, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(11, Suit::Hearts),
-            Card::new(12, Suit::Hearts),
-            Card::new(13, Suit::Hearts),
-        ];
+    fn test_bad_deck_leaves_state_untouched() {
+        let mut state = PokerGameState::new();
+        state.variant = Variant::ShortDeck;
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        let before = state.clone();
+
+        state.pre_deal_hook = Some(Rc::new(RefCell::new(|deck: &mut Deck| {
+            deck.cards[0] = Card::new(2, Suit::Spades);
+        })));
+        assert_eq!(
+            state.start_new_hand(),
+            Err("Deck is not a complete 36-card set")
+        );
+        assert_eq!(state.hand_number, before.hand_number);
+        assert_eq!(state.event_log, before.event_log);
+        assert_eq!(state.stage, GameStage::HandComplete);
+        assert_eq!(
+            state.players[0].get_hole_cards(),
+            before.players[0].get_hole_cards()
+        );
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::RoyalFlush);
+    #[test]
+    fn test_too_many_seats_for_deck_errors_before_dealing() {
+        let seats: Vec<(String, u64)> = (0..18).map(|i| (format!("P{i}"), 1000)).collect();
+        let named: Vec<(&str, u64)> = seats.iter().map(|(n, c)| (n.as_str(), *c)).collect();
+        let mut state = GameBuilder::new().players(&named).build();
+        state.variant = Variant::ShortDeck;
+        assert_eq!(state.start_new_hand(), Err("Deck exhausted"));
+        assert_eq!(state.hand_number, 0);
+        assert!(state.event_log.is_empty());
+        assert_eq!(state.stage, GameStage::WaitingToStart);
+        assert!(state.players.iter().all(|p| p.get_hole_cards().is_empty()));
     }
 
     #[test]
-    fn test_hand_evaluator_two_pair() {
-        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(10, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(5, Suit::Diamonds),
-            Card::new(10, Suit::Clubs),
-            Card::new(12, Suit::Spades),
-        ];
+    fn test_auto_action_error_is_returned() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.current_player, 1);
+        state.set_auto_action(0, AutoAction::CheckFold).unwrap();
+        state.deck.cards.truncate(3);
+
+        assert_eq!(
+            state.perform_action(PlayerAction::Call),
+            Err("Deck exhausted")
+        );
+        assert!(state.community_cards.is_empty());
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::TwoPair);
+    #[test]
+    fn test_session_keeps_table_rules() {
+        let mut original = PokerGameState::new_seeded(3);
+        original.chip_denomination = 5;
+        original.burn_cards = false;
+        original.burn_per_street = 2;
+        original.max_raises_per_street = Some(3);
+        original.run_twice_on_all_in = true;
+        original.hi_lo = true;
+        original.owe_missed_blinds = true;
+
+        let json = serde_json::to_string(&Session::capture(&original).unwrap()).unwrap();
+        let resumed = serde_json::from_str::<Session>(&json).unwrap().into_state();
+        assert_eq!(resumed.chip_denomination, 5);
+        assert!(!resumed.burn_cards);
+        assert_eq!(resumed.burn_per_street, 2);
+        assert_eq!(resumed.max_raises_per_street, Some(3));
+        assert!(resumed.run_twice_on_all_in);
+        assert!(resumed.hi_lo);
+        assert!(resumed.owe_missed_blinds);
+
+        let old_save = r#"{"variant":"TexasHoldem","seed":3,"hand_number":0,
+            "dealer_position":0,"small_blind":10,"big_blind":20,
+            "players":[["Alice",1000],["Bob",1000]]}"#;
+        let resumed = serde_json::from_str::<Session>(old_save)
+            .unwrap()
+            .into_state();
+        assert_eq!(resumed.chip_denomination, 1);
+        assert!(resumed.burn_cards);
+        assert!(!resumed.hi_lo);
     }
 
     #[test]
-    fn test_hand_evaluator_three_of_a_kind() {
-        let hole_cards = vec![Card::new(5, Suit::Spades), Card::new(8, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(5, Suit::Diamonds),
-            Card::new(5, Suit::Clubs),
-            Card::new(12, Suit::Spades),
-        ];
+    fn test_win_reason_and_label() {
+        let mut state = PokerGameState::new();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.reason, WinReason::Fold);
+        assert_eq!(result.how_won(), "uncontested");
+
+        let mut state = hilo_hand(
+            Variant::TexasHoldem,
+            &["A♠ A♥", "7♣ 2♦"],
+            Some("3♠ 9♥ J♦ K♣ 5♠"),
+            (10, 20),
+        );
+        state.hi_lo = false;
+        check_down(&mut state);
+        let result = state.last_result.clone().unwrap();
+        assert_eq!(result.reason, WinReason::Showdown);
+        assert_eq!(result.winners, vec![0]);
+        assert_eq!(result.how_won(), "with Pair");
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::ThreeOfAKind);
+    #[test]
+    fn test_straddle_skips_and_rejects_sitting_out_seats() {
+        let mut state = GameBuilder::new()
+            .players(&[
+                ("Alice", 1000),
+                ("Bob", 1000),
+                ("Carol", 1000),
+                ("Dave", 1000),
+                ("Erin", 1000),
+            ])
+            .dealer(0)
+            .build();
+        state.players[4].sit_out(true);
+        assert_eq!(state.post_straddle(4, 40), Err("Player is sitting out"));
+
+        state.post_straddle(3, 40).unwrap();
+        state.start_new_hand().unwrap();
+        assert_eq!(state.current_player, 0);
+        assert!(!state.legal_actions().is_empty());
+        state.perform_action(PlayerAction::Call).unwrap();
+
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000), ("Carol", 1000)])
+            .dealer(0)
+            .build();
+        state.post_straddle(0, 40).unwrap();
+        state.players[0].sit_out(true);
+        assert_eq!(state.start_new_hand(), Err("Player is sitting out"));
+        assert_eq!(state.hand_number, 0);
+        assert_eq!(state.pot, 0);
     }
 
     #[test]
-    fn test_hand_evaluator_ace_low_straight() {
-        let hole_cards = vec![Card::new(2, Suit::Spades), Card::new(3, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(4, Suit::Diamonds),
-            Card::new(5, Suit::Clubs),
-            Card::new(14, Suit::Spades),
-        ];
+    fn test_run_it_twice_rejected_while_betting_is_open() {
+        let mut state = PokerGameState::new_seeded(5);
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::RaiseTo(100)).unwrap();
+        let pot = state.pot;
+
+        assert_eq!(state.run_it_twice(), Err("Betting is still open"));
+        assert_eq!(state.stage, GameStage::Preflop);
+        assert_eq!(state.pot, pot);
+        assert!(state.community_cards.is_empty());
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::Straight);
-        assert_eq!(evaluated.primary_values, vec![5, 4, 3, 2, 1]);
+    #[test]
+    fn test_session_capture_refused_mid_hand() {
+        let mut state = PokerGameState::new_seeded(8);
+        let total: u64 = state.players.iter().map(Player::get_chips).sum();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(
+            Session::capture(&state),
+            Err("Cannot save a session mid-hand")
+        );
+
+        state.perform_action(PlayerAction::BetTo(40)).unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        let resumed = Session::capture(&state).unwrap().into_state();
+        let saved: u64 = resumed.players.iter().map(Player::get_chips).sum();
+        assert_eq!(saved, total);
     }
 
     #[test]
-    fn test_hand_evaluator_straight_with_higher_non_straight_cards() {
-        let hole_cards = vec![Card::new(14, Suit::Spades), Card::new(13, Suit::Hearts)];
-        let community_cards = vec![
-            Card::new(11, Suit::Diamonds),
-            Card::new(10, Suit::Clubs),
-            Card::new(9, Suit::Spades),
-            Card::new(8, Suit::Hearts),
-            Card::new(7, Suit::Diamonds),
-        ];
+    fn test_advertised_min_raise_is_a_minimum_raise() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000), ("Carol", 1000)])
+            .dealer(0)
+            .blinds(10, 20)
+            .build();
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Call).unwrap();
+        assert_eq!(state.current_player, 1);
+
+        let raise = state
+            .legal_actions()
+            .into_iter()
+            .find(|a| a.action == PlayerAction::Raise)
+            .unwrap();
+        let sizing = raise.sizing.unwrap();
+        assert_eq!(sizing.min, 30);
+        assert_eq!(state.set_bet_amount(sizing.min), sizing.min);
+        state.perform_action(PlayerAction::Raise).unwrap();
+        assert_eq!(state.to_call, 40);
+        assert_eq!(state.players[1].get_current_bet(), 40);
+        assert_eq!(state.get_call_amount(), 20);
+    }
 
-        let evaluated = PokerHandEvaluator::evaluate(&hole_cards, &community_cards);
-        assert_eq!(evaluated.rank, HandRank::Straight);
-        assert_eq!(evaluated.primary_values, vec![11, 10, 9, 8, 7]);
+    #[test]
+    fn test_start_new_hand_reuses_deck_buffers() {
+        let mut state = PokerGameState::new_seeded(2);
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        let buffers = [state.deck.cards.as_ptr(), state.spare_deck.cards.as_ptr()];
+
+        state.start_new_hand().unwrap();
+        state.perform_action(PlayerAction::Fold).unwrap();
+        assert_eq!(
+            [state.spare_deck.cards.as_ptr(), state.deck.cards.as_ptr()],
+            buffers
+        );
+
+        let leftover = state.deck.cards.clone();
+        state.pre_deal_hook = Some(Rc::new(RefCell::new(|deck: &mut Deck| {
+            deck.cards.pop();
+        })));
+        assert!(state.start_new_hand().is_err());
+        assert_eq!(state.deck.cards, leftover);
+    }
+
+    #[test]
+    fn test_busted_seat_is_left_out_of_the_hand() {
+        let mut state = GameBuilder::new()
+            .players(&[("Alice", 1000), ("Bob", 1000), ("Carol", 0)])
+            .dealer(0)
+            .blinds(10, 20)
+            .build();
+        state.start_new_hand().unwrap();
+
+        let carol = &state.players[2];
+        assert!(carol.is_folded());
+        assert!(carol.get_hole_cards().is_empty());
+        assert_eq!(carol.get_total_invested(), 0);
+        assert_eq!(state.players[1].get_current_bet(), 10);
+        assert_eq!(state.players[0].get_current_bet(), 20);
+        assert_eq!(state.to_call, 20);
+        assert_eq!(state.current_player, 1);
+
+        state.perform_action(PlayerAction::Fold).unwrap();
+        assert_eq!(state.players[0].get_chips(), 1010);
+        assert_eq!(state.players[2].get_stats().hands_played, 0);
+    }
+
+    #[test]
+    fn test_run_it_twice_halves_respect_chip_denomination() {
+        for seed in 0..10 {
+            let mut deck = Deck::new();
+            deck.shuffle_with_rng(&mut StdRng::seed_from_u64(seed));
+            let mut state = GameBuilder::new()
+                .players(&[("Alice", 1003), ("Bob", 1003)])
+                .deck(deck)
+                .build();
+            state.chip_denomination = 5;
+            state.run_twice_on_all_in = true;
+            state.start_new_hand().unwrap();
+            state.perform_action(PlayerAction::AllIn).unwrap();
+            state.perform_action(PlayerAction::AllIn).unwrap();
+            assert_eq!(state.stage, GameStage::HandComplete);
+
+            let awards: Vec<u64> = state
+                .event_log
+                .iter()
+                .filter_map(|e| match e {
+                    HandEvent::PotAwarded { amount, .. } => Some(*amount),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(awards.iter().sum::<u64>(), 2006);
+            assert!(
+                awards[1..].iter().all(|amount| amount % 5 == 0),
+                "{awards:?}"
+            );
+        }
     }
 }
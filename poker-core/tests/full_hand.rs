@@ -0,0 +1,74 @@
+use poker_core::{
+    Deck, GameBuilder, GameStage, PlayerAction, PokerGameState, PokerHandEvaluator, WinReason,
+    INITIAL_CHIPS,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn seeded_deck(seed: u64) -> Deck {
+    let mut deck = Deck::new();
+    deck.shuffle_with_rng(&mut StdRng::seed_from_u64(seed));
+    deck
+}
+
+fn stacks(state: &PokerGameState) -> u64 {
+    state.players.iter().map(|p| p.get_chips()).sum()
+}
+
+/// Call or check every street, opening the betting on the flop so chips move on
+/// more than one street.
+fn play_to_showdown(state: &mut PokerGameState) {
+    while state.stage != GameStage::HandComplete {
+        let action = if state.stage == GameStage::Flop && state.can_bet() {
+            state.set_bet_amount(state.min_bet);
+            PlayerAction::Bet
+        } else if state.can_check() {
+            PlayerAction::Check
+        } else {
+            PlayerAction::Call
+        };
+        state
+            .perform_action(action)
+            .unwrap_or_else(|e| panic!("{action:?} rejected in {:?}: {e}", state.stage));
+    }
+}
+
+#[test]
+fn test_seeded_hand_plays_to_showdown() {
+    for seed in 0..20 {
+        let mut state = GameBuilder::new().deck(seeded_deck(seed)).build();
+        state.start_new_hand().unwrap();
+        assert_eq!(stacks(&state) + state.pot, 2 * INITIAL_CHIPS);
+
+        play_to_showdown(&mut state);
+
+        assert_eq!(state.community_cards.len(), 5);
+        assert_eq!(stacks(&state), 2 * INITIAL_CHIPS);
+
+        let hands: Vec<_> = state
+            .players
+            .iter()
+            .map(|p| PokerHandEvaluator::evaluate(p.get_hole_cards(), &state.community_cards))
+            .collect();
+        let best = hands.iter().max().unwrap();
+        let expected: Vec<usize> = (0..hands.len()).filter(|&i| hands[i] == *best).collect();
+
+        let result = state.last_result.as_ref().unwrap();
+        assert_eq!(result.reason, WinReason::Showdown);
+        assert_eq!(result.winners, expected, "seed {seed}");
+        assert_eq!(result.best.as_ref(), Some(best));
+        assert_eq!(result.amounts.iter().sum::<u64>(), state.pot);
+
+        let chips: Vec<u64> = state.players.iter().map(|p| p.get_chips()).collect();
+        if expected.len() == 1 {
+            let winner = expected[0];
+            assert!(chips[winner] > INITIAL_CHIPS);
+            assert_eq!(
+                chips[winner] - INITIAL_CHIPS,
+                INITIAL_CHIPS - chips[1 - winner]
+            );
+        } else {
+            assert_eq!(chips, vec![INITIAL_CHIPS; 2]);
+        }
+    }
+}
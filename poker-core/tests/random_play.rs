@@ -0,0 +1,86 @@
+use poker_core::{Deck, GameBuilder, GameStage};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Play one hand per seed with random seats, stacks and legal actions, checking
+/// that every hand finishes, no chips are created or lost, and nobody collects
+/// more than their side pots allow.
+#[test]
+fn test_random_legal_play_conserves_chips() {
+    for seed in 0..3000 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let seats = rng.gen_range(2..=4);
+        let players: Vec<(String, u64)> = (0..seats)
+            .map(|i| (format!("P{i}"), rng.gen_range(15..=600)))
+            .collect();
+        let named: Vec<(&str, u64)> = players.iter().map(|(n, c)| (n.as_str(), *c)).collect();
+        let total: u64 = named.iter().map(|&(_, chips)| chips).sum();
+
+        let mut deck = Deck::new();
+        deck.shuffle_with_rng(&mut rng);
+        let mut state = GameBuilder::new()
+            .players(&named)
+            .dealer(rng.gen_range(0..seats))
+            .deck(deck)
+            .build();
+        state.run_twice_on_all_in = rng.gen_bool(0.25);
+        state.start_new_hand().unwrap();
+
+        let mut steps = 0;
+        while state.stage != GameStage::HandComplete {
+            steps += 1;
+            assert!(steps < 200, "seed {seed}: hand did not finish");
+            let stacks: u64 = state.players.iter().map(|p| p.get_chips()).sum();
+            assert_eq!(
+                stacks + state.pot,
+                total,
+                "seed {seed}: chips changed mid-hand"
+            );
+
+            let legal = state.legal_actions();
+            if legal.is_empty() {
+                state.advance_if_no_action().unwrap();
+                continue;
+            }
+            let choice = legal[rng.gen_range(0..legal.len())];
+            if let Some(sizing) = choice.sizing {
+                assert!(
+                    sizing.min <= sizing.max,
+                    "seed {seed}: empty sizing for {:?}",
+                    choice.action
+                );
+                state.set_bet_amount(rng.gen_range(sizing.min..=sizing.max));
+            }
+            if let Err(e) = state.perform_action(choice.action) {
+                panic!("seed {seed}: legal {:?} rejected: {e}", choice.action);
+            }
+        }
+
+        let stacks: u64 = state.players.iter().map(|p| p.get_chips()).sum();
+        assert_eq!(stacks, total, "seed {seed}: chips not conserved");
+        let result = state
+            .last_result
+            .as_ref()
+            .expect("finished hand has a result");
+        assert_eq!(
+            result.amounts.iter().sum::<u64>(),
+            state.pot,
+            "seed {seed}: pot not fully distributed"
+        );
+
+        // A player can win at most what each opponent put in, up to their own
+        // stake. Uncalled bets have already gone back, so the totals are final.
+        let invested: Vec<u64> = state
+            .players
+            .iter()
+            .map(|p| p.get_total_invested())
+            .collect();
+        for (&winner, &amount) in result.winners.iter().zip(&result.amounts) {
+            let cap: u64 = invested.iter().map(|&i| i.min(invested[winner])).sum();
+            assert!(
+                amount <= cap,
+                "seed {seed}: seat {winner} won {amount} but could win at most {cap}"
+            );
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use poker_core::{Card, PlayerAction, PokerGameState};
+use poker_core::{
+    Card, GameObserver, GameStage, HandEvent, HandResult, PlayerAction, PokerGameState,
+};
 use slint::Weak;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -10,8 +12,6 @@ fn main() {
 
     app.set_player1_name("Alice".into());
     app.set_player2_name("Bob".into());
-    app.set_player1_chips(poker_core::INITIAL_CHIPS as f32);
-    app.set_player2_chips(poker_core::INITIAL_CHIPS as f32);
     app.set_pot_size(0.0);
     app.set_game_stage("Waiting".into());
     app.set_message("Click Start to begin".into());
@@ -38,6 +38,11 @@ fn main() {
 
     let game_weak = app.as_weak();
     let game = Rc::new(RefCell::new(PokerGame::new(game_weak.clone())));
+    {
+        let g = game.borrow();
+        app.set_player1_chips(g.state.players[0].get_chips() as f32);
+        app.set_player2_chips(g.state.players[1].get_chips() as f32);
+    }
 
     let game1 = game.clone();
     app.on_bet_changed(move |value| {
@@ -54,7 +59,7 @@ fn main() {
     let game3 = game.clone();
     app.on_fold(move || {
         let mut g = game3.borrow_mut();
-        if g.is_pending_action() {
+        if g.is_action_legal(PlayerAction::Fold) {
             g.perform_action(PlayerAction::Fold).ok();
         }
     });
@@ -62,7 +67,7 @@ fn main() {
     let game4 = game.clone();
     app.on_check(move || {
         let mut g = game4.borrow_mut();
-        if g.is_pending_action() {
+        if g.is_action_legal(PlayerAction::Check) {
             g.perform_action(PlayerAction::Check).ok();
         }
     });
@@ -70,7 +75,7 @@ fn main() {
     let game5 = game.clone();
     app.on_call(move || {
         let mut g = game5.borrow_mut();
-        if g.is_pending_action() {
+        if g.is_action_legal(PlayerAction::Call) {
             g.perform_action(PlayerAction::Call).ok();
         }
     });
@@ -78,7 +83,7 @@ fn main() {
     let game6 = game.clone();
     app.on_bet(move || {
         let mut g = game6.borrow_mut();
-        if g.is_pending_action() {
+        if g.is_action_legal(PlayerAction::Bet) {
             g.perform_action(PlayerAction::Bet).ok();
         }
     });
@@ -86,7 +91,7 @@ fn main() {
     let game7 = game.clone();
     app.on_raise(move || {
         let mut g = game7.borrow_mut();
-        if g.is_pending_action() {
+        if g.is_action_legal(PlayerAction::Raise) {
             g.perform_action(PlayerAction::Raise).ok();
         }
     });
@@ -97,37 +102,124 @@ fn main() {
     }
 }
 
+/// Keeps the board slots in step with the cards as they are dealt.
+struct BoardObserver {
+    game_weak: Weak<PokerApp>,
+}
+
+impl GameObserver for BoardObserver {
+    fn on_hand_start(&mut self, _hand_number: u64) {
+        if let Some(ui) = self.game_weak.upgrade() {
+            for slot in 0..5 {
+                set_board_card(&ui, slot, None);
+            }
+        }
+    }
+
+    fn on_street(&mut self, stage: GameStage, cards: &[Card]) {
+        // `stage` says how long the board is now, and `cards` are its last
+        // cards, so a whole run-out dealt at once still lands in slots 0-4.
+        let board_len = match stage {
+            GameStage::Flop => 3,
+            GameStage::Turn => 4,
+            _ => 5,
+        };
+        let first = board_len - cards.len().min(board_len);
+        if let Some(ui) = self.game_weak.upgrade() {
+            for (i, card) in cards.iter().enumerate() {
+                set_board_card(&ui, first + i, Some(card));
+            }
+        }
+    }
+}
+
+fn set_board_card(ui: &PokerApp, slot: usize, card: Option<&Card>) {
+    let text: slint::SharedString = card.map(ToString::to_string).unwrap_or_default().into();
+    let red = card.is_some_and(|c| c.is_red());
+    match slot {
+        0 => {
+            ui.set_flop1(text);
+            ui.set_flop1_red(red);
+        }
+        1 => {
+            ui.set_flop2(text);
+            ui.set_flop2_red(red);
+        }
+        2 => {
+            ui.set_flop3(text);
+            ui.set_flop3_red(red);
+        }
+        3 => {
+            ui.set_turn(text);
+            ui.set_turn_red(red);
+        }
+        _ => {
+            ui.set_river(text);
+            ui.set_river_red(red);
+        }
+    }
+}
+
 pub struct PokerGame {
-    state: poker_core::PokerGameState,
+    state: PokerGameState,
     game_weak: Weak<PokerApp>,
 }
 
 impl PokerGame {
     pub fn new(game_weak: Weak<PokerApp>) -> Self {
-        PokerGame {
-            state: poker_core::PokerGameState::new(),
-            game_weak,
-        }
+        let mut state = PokerGameState::new();
+        state.add_observer(Rc::new(RefCell::new(BoardObserver {
+            game_weak: game_weak.clone(),
+        })));
+        PokerGame { state, game_weak }
     }
 
     pub fn start_new_hand(&mut self) -> Result<(), &'static str> {
         self.state.start_new_hand()?;
-        self.update_ui("New hand started".to_string());
+        let message = self
+            .state
+            .event_log
+            .first()
+            .map(|event| self.state.narrate(event))
+            .unwrap_or_default();
+        self.update_ui(message);
         Ok(())
     }
 
     pub fn perform_action(&mut self, action: PlayerAction) -> Result<(), &'static str> {
-        let message = self.state.perform_action(action)?;
+        let mut message = self.state.perform_action(action)?;
+        if self.state.stage == GameStage::HandComplete {
+            if let Some(result) = &self.state.last_result {
+                message = format!("{message}. {}", self.describe_result(result));
+            }
+        }
         self.update_ui(message);
         Ok(())
     }
 
+    fn describe_result(&self, result: &HandResult) -> String {
+        let how = result.how_won();
+        result
+            .winners
+            .iter()
+            .zip(&result.amounts)
+            .map(|(&player, &amount)| {
+                let award = HandEvent::PotAwarded { player, amount };
+                format!("{} {how}", self.state.narrate(&award))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     pub fn set_bet_amount(&mut self, amount: f32) {
-        self.state.set_bet_amount(amount);
+        let actual = self.state.set_bet_amount(amount as u64);
+        if let Some(ui) = self.game_weak.upgrade() {
+            ui.set_bet_amount(actual as f32);
+        }
     }
 
-    pub fn is_pending_action(&self) -> bool {
-        self.state.is_pending_action()
+    pub fn is_action_legal(&self, action: PlayerAction) -> bool {
+        self.state.is_action_legal(action)
     }
 
     pub fn update_ui(&mut self, message: String) {
@@ -151,8 +243,12 @@ impl PokerGame {
             ui.set_dealer_position(self.state.dealer_position as i32);
             ui.set_message(message.into());
 
+            // Catch the board up with the state, e.g. after run-it-twice puts
+            // the first run's board back.
+            for slot in 0..5 {
+                set_board_card(&ui, slot, self.state.community_cards.get(slot));
+            }
             self.update_player_cards(&ui);
-            self.update_community_cards(&ui);
             self.update_player_status(&ui);
             self.update_action_controls(&ui);
         }
@@ -173,7 +269,7 @@ impl PokerGame {
         }
     }
 
-    fn get_hole_card(&self, player_idx: usize, card_idx: usize) -> Option<&poker_core::Card> {
+    fn get_hole_card(&self, player_idx: usize, card_idx: usize) -> Option<&Card> {
         self.state
             .players
             .get(player_idx)
@@ -193,37 +289,6 @@ impl PokerGame {
             .unwrap_or(false)
     }
 
-    fn update_community_cards(&self, ui: &PokerApp) {
-        ui.set_flop1(self.community_card_string(0));
-        ui.set_flop2(self.community_card_string(1));
-        ui.set_flop3(self.community_card_string(2));
-        ui.set_turn(self.community_card_string(3));
-        ui.set_river(self.community_card_string(4));
-
-        ui.set_flop1_red(self.community_card_red(0));
-        ui.set_flop2_red(self.community_card_red(1));
-        ui.set_flop3_red(self.community_card_red(2));
-        ui.set_turn_red(self.community_card_red(3));
-        ui.set_river_red(self.community_card_red(4));
-    }
-
-    fn get_community_card(&self, card_idx: usize) -> Option<&poker_core::Card> {
-        self.state.community_cards.get(card_idx)
-    }
-
-    fn community_card_string(&self, card_idx: usize) -> slint::SharedString {
-        self.get_community_card(card_idx)
-            .map(ToString::to_string)
-            .unwrap_or_default()
-            .into()
-    }
-
-    fn community_card_red(&self, card_idx: usize) -> bool {
-        self.get_community_card(card_idx)
-            .map(|c| c.is_red())
-            .unwrap_or(false)
-    }
-
     fn update_player_status(&self, ui: &PokerApp) {
         if !self.state.players.is_empty() {
             ui.set_p1_acting(self.state.current_player == 0 && !self.state.players[0].is_folded());